@@ -0,0 +1,111 @@
+use std::collections::VecDeque;
+
+use crate::colour::Colour;
+use crate::grid::Grid;
+
+/// A move spreading across the board as a wave: the cells of the flooded region take on their
+/// new colour one step after another, in the order of their distance from the cell the flood
+/// starts from.
+#[derive(Clone, Debug)]
+pub struct Animation {
+    /// The colours of all cells before the move.
+    before: Vec<Colour>,
+
+    /// For every cell that changed colour, the number of steps until it shows its new colour.
+    steps: Vec<Option<u32>>,
+
+    last_step: u32,
+
+    /// When the animation started, as soon as it is known.
+    start: Option<f64>,
+
+    /// How many steps have been shown so far, including partially.
+    progress: f64,
+}
+
+impl Animation {
+    /// The transition from `before` to `after`, spreading from `origin`.  There is nothing to
+    /// animate if no cell changed colour.
+    pub fn new(before: &Grid, after: &Grid, origin: usize) -> Option<Self> {
+        let mut steps = vec![None; after.cells.len()];
+        let mut distances = vec![None; after.cells.len()];
+        let mut queue = VecDeque::from([(origin, 0)]);
+        distances[origin] = Some(0);
+
+        // The region that changed colour is connected, so it is enough to search the cells
+        // having the new colour of `origin`.
+        while let Some((cell, distance)) = queue.pop_front() {
+            if before.cells[cell] != after.cells[cell] {
+                steps[cell] = Some(distance);
+            }
+            for neighbour in after.neighbours(cell) {
+                if distances[neighbour].is_none() && after.cells[neighbour] == after.cells[origin] {
+                    distances[neighbour] = Some(distance + 1);
+                    queue.push_back((neighbour, distance + 1));
+                }
+            }
+        }
+
+        let last_step = steps.iter().flatten().copied().max()?;
+        Some(Self {
+            before: before.cells.clone(),
+            steps,
+            last_step,
+            start: None,
+            progress: 0.0,
+        })
+    }
+
+    /// Advance the animation to `now` at `speed` steps per second.  Returns whether it is over.
+    pub fn update(&mut self, now: f64, speed: f64) -> bool {
+        let start = *self.start.get_or_insert(now);
+        self.progress = (now - start) * speed;
+
+        self.progress > self.last_step as f64
+    }
+
+    /// Show the cells the wave has not reached yet in their old colour.
+    pub fn apply(&self, grid: &mut Grid) {
+        for (cell, step) in self.steps.iter().enumerate() {
+            if step.is_some_and(|step| step as f64 >= self.progress) {
+                grid.cells[cell] = self.before[cell];
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::topology::TopologyKind;
+
+    fn row(cells: Vec<Colour>) -> Grid {
+        Grid::new(TopologyKind::Square.create(1, cells.len()), cells)
+    }
+
+    #[test]
+    fn should_recolour_cells_in_order_of_distance() {
+        let (red, yellow, green) = (Colour::RED, Colour::YELLOW, Colour::GREEN);
+        let before = row(vec![red, red, red, yellow, green]);
+        let after = row(vec![yellow, yellow, yellow, yellow, green]);
+        let mut animation = Animation::new(&before, &after, 0).unwrap();
+
+        assert!(!animation.update(10.0, 2.0));
+        assert!(!animation.update(10.75, 2.0));
+        let mut grid = row(after.cells.clone());
+        animation.apply(&mut grid);
+        assert_eq!(grid.cells, vec![yellow, yellow, red, yellow, green]);
+
+        assert!(animation.update(11.5, 2.0));
+        let mut grid = row(after.cells.clone());
+        animation.apply(&mut grid);
+        assert_eq!(grid, after);
+    }
+
+    #[test]
+    fn should_not_animate_move_without_change() {
+        let grid = row(vec![Colour::RED, Colour::YELLOW]);
+
+        assert!(Animation::new(&grid, &grid, 0).is_none());
+    }
+}
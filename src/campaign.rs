@@ -0,0 +1,174 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::{allowed_clicks, Game};
+use crate::grid::Grid;
+use crate::storage;
+use crate::topology::{Connectivity, TopologyKind};
+
+const PROGRESS_FILE: &str = "campaign.toml";
+
+/// A fixed board, described by the parameters it is generated from.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Level {
+    pub size: u32,
+    pub number_of_colours: u32,
+    seed: u64,
+}
+
+impl Level {
+    const fn new(size: u32, number_of_colours: u32, seed: u64) -> Self {
+        Self {
+            size,
+            number_of_colours,
+            seed,
+        }
+    }
+
+    fn grid(&self) -> Grid {
+        Grid::generate_from_seed(self.size as usize, self.number_of_colours, self.seed)
+    }
+
+    pub fn allowed_clicks(&self) -> u32 {
+        allowed_clicks(
+            self.size,
+            self.number_of_colours,
+            TopologyKind::Square,
+            Connectivity::Edges,
+        )
+    }
+}
+
+/// The levels of the campaign, getting harder as they go.
+pub const LEVELS: [Level; 12] = [
+    Level::new(6, 3, 3),
+    Level::new(8, 3, 4),
+    Level::new(8, 4, 3),
+    Level::new(10, 4, 1),
+    Level::new(10, 5, 0),
+    Level::new(12, 4, 5),
+    Level::new(12, 5, 4),
+    Level::new(14, 5, 3),
+    Level::new(14, 6, 0),
+    Level::new(16, 6, 2),
+    Level::new(18, 7, 2),
+    Level::new(20, 8, 0),
+];
+
+/// The levels the player has completed, counting from 0.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Progress {
+    completed: BTreeSet<usize>,
+}
+
+pub struct Campaign {
+    /// Index of the level being played.
+    pub level: usize,
+
+    progress: Progress,
+}
+
+impl Campaign {
+    /// Continue with the first level that has not been completed yet.
+    pub fn resume() -> Self {
+        let progress: Progress = storage::load(PROGRESS_FILE);
+        let level = (0..LEVELS.len())
+            .find(|level| !progress.completed.contains(level))
+            .unwrap_or(0);
+
+        Self { level, progress }
+    }
+
+    /// Play the given level, counting from 0, if it has been unlocked.
+    pub fn start_at(level: usize) -> Result<Self, String> {
+        let mut campaign = Self::resume();
+
+        if level >= LEVELS.len() {
+            Err(format!("There are only {} levels.", LEVELS.len()))
+        } else if !campaign.is_unlocked(level) {
+            Err(format!("Level {} has not been unlocked yet.", level + 1))
+        } else {
+            campaign.level = level;
+            Ok(campaign)
+        }
+    }
+
+    /// The first level and every level following a completed one can be played.
+    pub fn is_unlocked(&self, level: usize) -> bool {
+        level == 0 || self.progress.completed.contains(&(level - 1))
+    }
+
+    pub fn is_completed(&self, level: usize) -> bool {
+        self.progress.completed.contains(&level)
+    }
+
+    pub fn current_level(&self) -> &Level {
+        &LEVELS[self.level]
+    }
+
+    pub fn create_game(&self) -> Game {
+        let level = self.current_level();
+        Game::from_grid(&level.grid(), level.allowed_clicks())
+    }
+
+    /// Mark the current level as completed and move on to the next one, if there is one.
+    pub fn complete_level(&mut self) {
+        if self.progress.completed.insert(self.level) {
+            storage::save(PROGRESS_FILE, &self.progress);
+        }
+
+        if self.level + 1 < LEVELS.len() {
+            self.level += 1;
+        }
+    }
+
+    pub fn is_last_level(&self) -> bool {
+        self.level + 1 == LEVELS.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::game::TOP_LEFT_CELL;
+    use crate::graph::Graph;
+    use crate::solver;
+
+    fn campaign(completed: &[usize]) -> Campaign {
+        Campaign {
+            level: 0,
+            progress: Progress {
+                completed: completed.iter().copied().collect(),
+            },
+        }
+    }
+
+    #[test]
+    fn should_get_harder_from_level_to_level() {
+        for levels in LEVELS.windows(2) {
+            assert!(levels[0].size <= levels[1].size);
+            assert!(levels[0].allowed_clicks() <= levels[1].allowed_clicks());
+        }
+    }
+
+    #[test]
+    fn should_be_possible_to_complete_levels_within_budget() {
+        // Solving the larger levels takes too long for a test.
+        for level in &LEVELS[..9] {
+            let graph = Graph::create(&level.grid());
+            let solution = solver::solve(&graph, &TOP_LEFT_CELL, solver::DEFAULT_SEARCH_LIMIT);
+
+            assert!(solution.moves.len() as u32 <= level.allowed_clicks());
+        }
+    }
+
+    #[test]
+    fn should_unlock_level_after_completed_one() {
+        let campaign = campaign(&[0, 1]);
+
+        assert!(campaign.is_unlocked(0));
+        assert!(campaign.is_unlocked(2));
+        assert!(!campaign.is_unlocked(3));
+    }
+}
@@ -0,0 +1,132 @@
+/// Longest gap between two frames (in seconds) that is still counted as playing time.
+///
+/// macroquad does not tell us when the window loses focus, but a hidden or minimised window
+/// stops rendering frames.  Treating such a gap as a pause keeps the player from losing time
+/// while they are not looking at the board.
+const MAXIMUM_FRAME_GAP: f64 = 0.5;
+
+/// A countdown clock measuring playing time.
+///
+/// The clock does not read the time itself.  Instead, `tick` is called once per frame with the
+/// current time, which keeps the clock independent of the rendering backend.
+#[derive(Debug, Clone)]
+pub struct Clock {
+    /// Time limit in seconds.
+    limit: f64,
+
+    /// Playing time that has elapsed so far, in seconds.
+    elapsed: f64,
+
+    /// The time passed to the most recent call of `tick`.
+    last_tick: Option<f64>,
+
+    paused: bool,
+}
+
+impl Clock {
+    pub fn new(limit: f64) -> Self {
+        Self {
+            limit,
+            elapsed: 0.0,
+            last_tick: None,
+            paused: false,
+        }
+    }
+
+    pub fn tick(&mut self, now: f64) {
+        if let Some(last_tick) = self.last_tick {
+            let delta = now - last_tick;
+            if !self.paused && delta <= MAXIMUM_FRAME_GAP {
+                self.elapsed = (self.elapsed + delta).min(self.limit);
+            }
+        }
+
+        self.last_tick = Some(now);
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed
+    }
+
+    pub fn remaining(&self) -> f64 {
+        self.limit - self.elapsed
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.remaining() <= 0.0
+    }
+}
+
+/// Format a number of seconds as minutes and seconds, e.g. `2:05`.
+pub fn format_time(seconds: f64) -> String {
+    let seconds = seconds.max(0.0).ceil() as u64;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_count_time_between_ticks() {
+        let mut clock = Clock::new(10.0);
+
+        clock.tick(1.0);
+        clock.tick(1.25);
+        clock.tick(1.5);
+
+        assert_eq!(clock.elapsed(), 0.5);
+        assert_eq!(clock.remaining(), 9.5);
+    }
+
+    #[test]
+    fn should_not_count_time_while_paused() {
+        let mut clock = Clock::new(10.0);
+
+        clock.tick(1.0);
+        clock.toggle_pause();
+        clock.tick(1.25);
+        clock.toggle_pause();
+        clock.tick(1.5);
+
+        assert_eq!(clock.elapsed(), 0.25);
+    }
+
+    #[test]
+    fn should_ignore_long_gaps_between_frames() {
+        let mut clock = Clock::new(10.0);
+
+        clock.tick(1.0);
+        clock.tick(5.0);
+        clock.tick(5.25);
+
+        assert_eq!(clock.elapsed(), 0.25);
+    }
+
+    #[test]
+    fn should_expire_at_the_time_limit() {
+        let mut clock = Clock::new(0.5);
+
+        for i in 0..10 {
+            clock.tick(0.1 * i as f64);
+        }
+
+        assert!(clock.is_expired());
+        assert_eq!(clock.remaining(), 0.0);
+    }
+
+    #[test]
+    fn should_format_minutes_and_seconds() {
+        assert_eq!(format_time(125.0), "2:05");
+        assert_eq!(format_time(0.2), "0:01");
+        assert_eq!(format_time(-3.0), "0:00");
+    }
+}
@@ -1,22 +1,227 @@
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
-pub enum Colour {
-    Red,
-    Yellow,
-    Green,
-    LightBrown,
-    Purple,
-    Cyan,
-    Blue,
-    Fuchsia,
+use std::fs;
+use std::path::Path;
+
+/// A colour of the board, given by its index into the palette.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct Colour(pub usize);
+
+/// Names of the classic colours of the default palette.
+#[cfg(test)]
+impl Colour {
+    pub const RED: Colour = Colour(0);
+    pub const YELLOW: Colour = Colour(1);
+    pub const GREEN: Colour = Colour(2);
+    pub const BLUE: Colour = Colour(6);
+    pub const FUCHSIA: Colour = Colour(7);
+}
+
+/// Red, green and blue components of a colour.
+pub type Rgb = (u8, u8, u8);
+
+/// The classic colours followed by some more for larger numbers of colours.
+const DEFAULT_PALETTE: [Rgb; 16] = [
+    (255, 0, 0),
+    (255, 255, 0),
+    (0, 176, 0),
+    (255, 204, 102),
+    (128, 0, 128),
+    (0, 255, 255),
+    (0, 0, 255),
+    (255, 0, 255),
+    (255, 128, 0),
+    (0, 96, 0),
+    (0, 0, 128),
+    (128, 128, 128),
+    (255, 255, 255),
+    (128, 0, 0),
+    (128, 128, 0),
+    (0, 128, 128),
+];
+
+/// Read a colour written as `#rrggbb` in hexadecimal.  The `#` is optional.
+pub fn parse_rgb(text: &str) -> Option<Rgb> {
+    let digits = text.strip_prefix('#').unwrap_or(text);
+    // `from_str_radix` would accept signs as well.
+    if !digits.chars().all(|digit| digit.is_ascii_hexdigit()) {
+        return None;
+    }
+    let component = |i: usize| {
+        digits
+            .get(2 * i..2 * i + 2)
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+    };
+    match (digits.len(), component(0), component(1), component(2)) {
+        (6, Some(r), Some(g), Some(b)) => Some((r, g, b)),
+        _ => None,
+    }
 }
 
-pub const ALL_COLOURS: [Colour; 8] = [
-    Colour::Red,
-    Colour::Yellow,
-    Colour::Green,
-    Colour::LightBrown,
-    Colour::Purple,
-    Colour::Cyan,
-    Colour::Blue,
-    Colour::Fuchsia,
+/// Whether black stands out more than white in front of `(r, g, b)`, judged by its luma.
+pub fn is_light((r, g, b): Rgb) -> bool {
+    0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32 > 127.5
+}
+
+/// Eight colours told apart by people with deuteranopia, based on the palette by Okabe and Ito.
+const DEUTERANOPIA_PALETTE: [Rgb; 8] = [
+    (230, 159, 0),
+    (86, 180, 233),
+    (0, 158, 115),
+    (240, 228, 66),
+    (0, 114, 178),
+    (213, 94, 0),
+    (204, 121, 167),
+    (255, 255, 255),
 ];
+
+/// Eight colours told apart by people with protanopia, who see red as rather dark.
+const PROTANOPIA_PALETTE: [Rgb; 8] = [
+    (255, 176, 0),
+    (100, 143, 255),
+    (0, 158, 115),
+    (255, 255, 255),
+    (120, 94, 240),
+    (254, 97, 0),
+    (220, 38, 127),
+    (136, 204, 238),
+];
+
+/// Eight colours told apart by people with tritanopia, who confuse blue with green and yellow
+/// with violet.
+const TRITANOPIA_PALETTE: [Rgb; 8] = [
+    (220, 50, 32),
+    (0, 150, 150),
+    (255, 140, 170),
+    (255, 255, 255),
+    (128, 0, 0),
+    (0, 90, 90),
+    (200, 0, 120),
+    (150, 150, 150),
+];
+
+/// What the colours of the board look like.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Palette {
+    colours: Vec<Rgb>,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            colours: DEFAULT_PALETTE.to_vec(),
+        }
+    }
+}
+
+impl Palette {
+    /// The built-in palettes, which can be chosen by name, and switched between in the window.
+    #[cfg(any(feature = "gui", test))]
+    pub const NAMES: [&'static str; 4] = ["default", "deuteranopia", "protanopia", "tritanopia"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        let base = match name {
+            "default" => return Some(Self::default()),
+            "deuteranopia" => DEUTERANOPIA_PALETTE,
+            "protanopia" => PROTANOPIA_PALETTE,
+            "tritanopia" => TRITANOPIA_PALETTE,
+            _ => return None,
+        };
+
+        // Darker shades of the same colours make up the second half, so there are as many
+        // colours as in the default palette.
+        let darker = base.map(|(r, g, b)| (r / 2, g / 2, b / 2));
+        Some(Self {
+            colours: base.into_iter().chain(darker).collect(),
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|error| format!("Cannot read palette {}: {}", path.display(), error))?;
+
+        Self::parse(&text)
+    }
+
+    /// Read colours written as `#rrggbb` in hexadecimal, separated by white space.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let colours = text
+            .split_whitespace()
+            .map(|word| {
+                parse_rgb(word).ok_or_else(|| format!("Invalid color '{}' in palette.", word))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if colours.is_empty() {
+            Err("The palette does not contain any colors.".to_string())
+        } else {
+            Ok(Self { colours })
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.colours.len()
+    }
+
+    /// All colours of the palette, in order.
+    #[cfg(test)]
+    pub fn colours(&self) -> Vec<Colour> {
+        (0..self.len()).map(Colour).collect()
+    }
+
+    pub fn rgb(&self, colour: Colour) -> Rgb {
+        self.colours[colour.0]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_start_default_palette_with_classic_colours() {
+        let palette = Palette::default();
+
+        assert_eq!(palette.rgb(Colour::RED), (255, 0, 0));
+        assert_eq!(palette.rgb(Colour::FUCHSIA), (255, 0, 255));
+        assert_eq!(palette.len(), 16);
+    }
+
+    #[test]
+    fn should_offer_as_many_distinct_colours_in_every_built_in_palette() {
+        for name in Palette::NAMES {
+            let palette = Palette::from_name(name).unwrap();
+            let distinct: std::collections::HashSet<_> = palette
+                .colours()
+                .into_iter()
+                .map(|c| palette.rgb(c))
+                .collect();
+
+            assert_eq!(palette.len(), Palette::default().len());
+            assert_eq!(distinct.len(), palette.len());
+        }
+    }
+
+    #[test]
+    fn should_parse_palette() {
+        let palette = Palette::parse("#000000 #ff8000\n  1a2B3c\n").unwrap();
+
+        assert_eq!(palette.colours(), vec![Colour(0), Colour(1), Colour(2)]);
+        assert_eq!(palette.rgb(Colour(1)), (255, 128, 0));
+        assert_eq!(palette.rgb(Colour(2)), (26, 43, 60));
+    }
+
+    #[test]
+    fn should_tell_light_from_dark_colours() {
+        assert!(is_light((255, 255, 0)));
+        assert!(is_light((255, 255, 255)));
+        assert!(!is_light((0, 0, 255)));
+        assert!(!is_light((128, 0, 128)));
+    }
+
+    #[test]
+    fn should_reject_invalid_colours() {
+        assert!(Palette::parse("#12345").is_err());
+        assert!(Palette::parse("#12345g").is_err());
+        assert!(Palette::parse("+1+2+3").is_err());
+        assert!(Palette::parse("").is_err());
+    }
+}
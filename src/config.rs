@@ -0,0 +1,256 @@
+use std::fmt;
+use std::fs;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::generator::GeneratorKind;
+use crate::mode::Mode;
+use crate::storage;
+use crate::topology::TopologyKind;
+
+const CONFIG_FILE: &str = "config.toml";
+
+/// A key that can be bound to an action.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Key {
+    /// A letter or a digit, letters in lower case.
+    Character(char),
+    Space,
+    Tab,
+    Enter,
+    Escape,
+    Backspace,
+    Function(u8),
+}
+
+impl FromStr for Key {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let lower = name.to_ascii_lowercase();
+        let mut characters = lower.chars();
+
+        match (characters.next(), characters.next()) {
+            (Some(character), None) if character.is_ascii_alphanumeric() => {
+                return Ok(Key::Character(character))
+            }
+            _ => (),
+        }
+
+        match lower.as_str() {
+            "space" => Ok(Key::Space),
+            "tab" => Ok(Key::Tab),
+            "enter" => Ok(Key::Enter),
+            "escape" => Ok(Key::Escape),
+            "backspace" => Ok(Key::Backspace),
+            _ => lower
+                .strip_prefix('f')
+                .and_then(|number| number.parse().ok())
+                .filter(|number| (1..=12).contains(number))
+                .map(Key::Function)
+                .ok_or_else(|| {
+                    format!(
+                        "Unknown key '{}'; use a letter, a digit, space, tab, enter, escape, \
+                         backspace or f1 to f12.",
+                        name
+                    )
+                }),
+        }
+    }
+}
+
+impl TryFrom<String> for Key {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        name.parse()
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Key::Character(character) => write!(f, "{}", character),
+            Key::Space => write!(f, "space"),
+            Key::Tab => write!(f, "tab"),
+            Key::Enter => write!(f, "enter"),
+            Key::Escape => write!(f, "escape"),
+            Key::Backspace => write!(f, "backspace"),
+            Key::Function(number) => write!(f, "f{}", number),
+        }
+    }
+}
+
+impl From<Key> for String {
+    fn from(key: Key) -> Self {
+        key.to_string()
+    }
+}
+
+/// The keys for every action, any of which triggers it.  The terminal only knows some of the
+/// actions, and keys bound there take precedence over the keys of the colours.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Keybindings {
+    pub quit: Vec<Key>,
+    pub new_board: Vec<Key>,
+    pub pause: Vec<Key>,
+    pub palette: Vec<Key>,
+    pub theme: Vec<Key>,
+    pub symbols: Vec<Key>,
+    pub statistics: Vec<Key>,
+}
+
+impl Default for Keybindings {
+    fn default() -> Self {
+        Self {
+            quit: vec![Key::Character('q')],
+            new_board: vec![Key::Space, Key::Character('n'), Key::Character('r')],
+            pause: vec![Key::Character('p')],
+            palette: vec![Key::Character('c')],
+            theme: vec![Key::Character('t')],
+            symbols: vec![Key::Character('s')],
+            statistics: vec![Key::Tab],
+        }
+    }
+}
+
+/// Defaults for the command line arguments, read from `config.toml` in the configuration
+/// directory.  Arguments given on the command line take precedence.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(rename = "colors")]
+    pub number_of_colours: Option<u32>,
+    pub size: Option<u32>,
+    pub mode: Option<String>,
+    pub topology: Option<String>,
+    pub generator: Option<String>,
+    pub theme: Option<String>,
+    pub palette: Option<String>,
+    pub animation_speed: Option<f64>,
+    pub symbols: Option<bool>,
+    pub tui: Option<bool>,
+
+    #[serde(default)]
+    pub keys: Keybindings,
+}
+
+impl Config {
+    /// Read the configuration file, if there is one.  A broken file is reported and ignored.
+    pub fn load() -> Self {
+        let Some(path) = storage::config_file(CONFIG_FILE).filter(|path| path.exists()) else {
+            return Self::default();
+        };
+
+        fs::read_to_string(&path)
+            .map_err(|error| error.to_string())
+            .and_then(|text| Self::parse(&text))
+            .unwrap_or_else(|error| {
+                eprintln!("Ignoring configuration {}: {}", path.display(), error);
+                Self::default()
+            })
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let config: Self = toml::from_str(text).map_err(|error| error.to_string())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Check the values the command line would check, so that mistakes are blamed on the file.
+    fn validate(&self) -> Result<(), String> {
+        let one_of = |field: &str, value: &Option<String>, names: &[&str]| match value {
+            Some(value) if !names.contains(&value.as_str()) => Err(format!(
+                "Invalid {} '{}'; use one of {}.",
+                field,
+                value,
+                names.join(", ")
+            )),
+            _ => Ok(()),
+        };
+        one_of("mode", &self.mode, &Mode::NAMES)?;
+        one_of("topology", &self.topology, &TopologyKind::NAMES)?;
+        one_of("generator", &self.generator, &GeneratorKind::NAMES)?;
+
+        // The palette may be given on the command line, so it is checked along with the arguments.
+        if self.number_of_colours.is_some_and(|colours| colours < 3) {
+            return Err("The number of colors has to be at least 3.".to_string());
+        }
+        if self.size.is_some_and(|size| size < 2) {
+            return Err("The size has to be at least 2.".to_string());
+        }
+        if self
+            .animation_speed
+            .is_some_and(|speed| speed < 0.0 || speed.is_nan())
+        {
+            return Err("The animation speed cannot be negative.".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// The configuration in the format of the configuration file.
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("Cannot write the configuration")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_parse_config() {
+        let config = Config::parse(
+            r#"
+            colors = 8
+            theme = "tiles"
+            animation_speed = 0.0
+
+            [keys]
+            quit = ["Escape", "x"]
+            statistics = ["F2"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.number_of_colours, Some(8));
+        assert_eq!(config.theme.as_deref(), Some("tiles"));
+        assert_eq!(config.size, None);
+        assert_eq!(config.keys.quit, vec![Key::Escape, Key::Character('x')]);
+        assert_eq!(config.keys.statistics, vec![Key::Function(2)]);
+        assert_eq!(config.keys.pause, Keybindings::default().pause);
+        assert_eq!(
+            Config::parse("colors = 20").unwrap().number_of_colours,
+            Some(20)
+        );
+    }
+
+    #[test]
+    fn should_reject_invalid_config() {
+        assert!(Config::parse("colours = 8").is_err());
+        assert!(Config::parse("colors = 2").is_err());
+        assert!(Config::parse("mode = \"fast\"").is_err());
+        assert!(Config::parse("topology = \"cube\"").is_err());
+        assert!(Config::parse("animation_speed = -1.0").is_err());
+        assert!(Config::parse("[keys]\nquit = [\"ctrl\"]").is_err());
+        assert!(Config::parse("[keys]\nquit = [\"f13\"]").is_err());
+        assert!(Config::parse("[keys]\njump = [\"j\"]").is_err());
+    }
+
+    #[test]
+    fn should_read_printed_config() {
+        let config = Config {
+            number_of_colours: Some(6),
+            mode: Some("zen".to_string()),
+            animation_speed: Some(30.0),
+            symbols: Some(false),
+            ..Config::default()
+        };
+
+        assert_eq!(Config::parse(&config.to_toml()), Ok(config));
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, Local, NaiveDate};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::game::{allowed_clicks, Game, TOP_LEFT_CELL};
+use crate::graph::Graph;
+use crate::grid::Grid;
+use crate::solver;
+use crate::storage;
+use crate::topology::{Connectivity, TopologyKind};
+
+const RESULTS_FILE: &str = "daily.toml";
+
+const SIZES: [u32; 3] = [10, 12, 14];
+pub const NUMBERS_OF_COLOURS: [u32; 3] = [4, 5, 6];
+
+/// The best number of moves for each day, keyed by the date in ISO 8601 format.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DailyResults {
+    best: BTreeMap<String, u32>,
+}
+
+/// Everyone playing on the same day gets the same board.
+pub struct Daily {
+    pub date: NaiveDate,
+    seed: u64,
+    pub size: u32,
+    pub number_of_colours: u32,
+
+    /// The number of moves the solver needed.
+    pub par: u32,
+
+    /// Whether the board cannot be flooded in fewer moves than `par`.
+    optimal: bool,
+
+    /// The player's best result for `date`, if they have flooded the board before.
+    pub best: Option<u32>,
+}
+
+impl Daily {
+    pub fn today() -> Self {
+        Self::for_date(Local::now().date_naive())
+    }
+
+    /// The challenge for `date` with the player's best result for it.
+    pub fn for_date(date: NaiveDate) -> Self {
+        let results: DailyResults = storage::load(RESULTS_FILE);
+        Self {
+            best: results.best.get(&date.to_string()).copied(),
+            ..Self::generate(date)
+        }
+    }
+
+    /// The challenge for `date` as if it had not been played yet.
+    fn generate(date: NaiveDate) -> Self {
+        let seed = date.num_days_from_ce() as u64;
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let size = SIZES[rng.gen_range(0..SIZES.len())];
+        let number_of_colours = NUMBERS_OF_COLOURS[rng.gen_range(0..NUMBERS_OF_COLOURS.len())];
+
+        let mut daily = Self {
+            date,
+            seed,
+            size,
+            number_of_colours,
+            par: 0,
+            optimal: false,
+            best: None,
+        };
+
+        let graph = Graph::create(&daily.grid());
+        let solution = solver::solve(&graph, &TOP_LEFT_CELL, solver::DEFAULT_SEARCH_LIMIT);
+        daily.par = solution.moves.len() as u32;
+        daily.optimal = solution.optimal;
+
+        daily
+    }
+
+    fn grid(&self) -> Grid {
+        Grid::generate_from_seed(self.size as usize, self.number_of_colours, self.seed)
+    }
+
+    pub fn create_game(&self) -> Game {
+        let mut game = Game::from_grid(
+            &self.grid(),
+            allowed_clicks(
+                self.size,
+                self.number_of_colours,
+                TopologyKind::Square,
+                Connectivity::Edges,
+            ),
+        );
+        game.optimum = self.optimal.then_some(self.par);
+        game
+    }
+
+    /// Remember `moves` if it beats the previous best for the day.  Returns whether it did.
+    pub fn record(&mut self, moves: u32) -> bool {
+        if !beats(self.best, moves) {
+            return false;
+        }
+
+        self.best = Some(moves);
+
+        let mut results: DailyResults = storage::load(RESULTS_FILE);
+        results.best.insert(self.date.to_string(), moves);
+        storage::save(RESULTS_FILE, &results);
+
+        true
+    }
+}
+
+/// Whether `moves` is a new best result, given the previous `best`, if any.
+fn beats(best: Option<u32>, moves: u32) -> bool {
+    best.is_none_or(|best| moves < best)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_generate_the_same_board_on_the_same_day() {
+        let date = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        let daily = Daily::generate(date);
+        let other = Daily::generate(date);
+
+        assert_eq!(daily.size, other.size);
+        assert_eq!(daily.number_of_colours, other.number_of_colours);
+        assert_eq!(daily.par, other.par);
+        assert_eq!(daily.grid(), other.grid());
+    }
+
+    #[test]
+    fn should_only_record_fewer_moves_than_the_best() {
+        assert!(beats(None, 30));
+        assert!(beats(Some(30), 29));
+        assert!(!beats(Some(30), 30));
+        assert!(!beats(Some(30), 31));
+    }
+}
@@ -0,0 +1,136 @@
+use std::ops::RangeInclusive;
+
+use rand::rngs::StdRng;
+
+use crate::game::{Game, Settings, MAXIMUM_ATTEMPTS};
+use crate::generator::{BoardGenerator, Cohesive, GeneratorKind};
+use crate::solver;
+
+/// How far to search for a solution when estimating the difficulty of a board.  The estimate is
+/// an upper bound of the optimal number of moves.
+const ESTIMATE_LIMIT: usize = 10_000;
+
+/// How much to change the cohesion of the next board when a board turns out too easy or too hard.
+const COHESION_STEP: f64 = 0.05;
+
+/// Presets for how many of the allowed moves flooding a board should take.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    pub const NAMES: [&'static str; 3] = ["easy", "medium", "hard"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "easy" => Some(Difficulty::Easy),
+            "medium" => Some(Difficulty::Medium),
+            "hard" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+
+    /// The share of the move budget the estimated number of moves should take.
+    fn target(self) -> RangeInclusive<f64> {
+        match self {
+            Difficulty::Easy => 0.0..=0.7,
+            Difficulty::Medium => 0.7..=0.85,
+            Difficulty::Hard => 0.85..=1.0,
+        }
+    }
+
+    /// The cohesion of the first board to try.  Clustered colours make for easier boards.
+    fn initial_cohesion(self) -> f64 {
+        match self {
+            Difficulty::Easy => 0.3,
+            Difficulty::Medium => 0.15,
+            Difficulty::Hard => 0.0,
+        }
+    }
+
+    /// Generate boards until the estimated number of moves falls into the target range.  Uniform
+    /// boards are made more or less clustered as needed, other generators are left as they are.
+    /// Should that take too long, the board closest to the target range is used instead.
+    pub fn create_game(self, settings: &Settings, rng: &mut StdRng) -> Game {
+        let target = self.target();
+        let mut cohesion = self.initial_cohesion();
+        let mut closest: Option<(f64, Game)> = None;
+
+        for _ in 0..MAXIMUM_ATTEMPTS {
+            let generator: Box<dyn BoardGenerator> = match settings.generator {
+                GeneratorKind::Uniform => Box::new(Cohesive { cohesion }),
+                kind => kind.create(),
+            };
+            let game = Game::generate(settings, &*generator, rng);
+            let solution = solver::solve(&game.graph, &game.start, ESTIMATE_LIMIT);
+            let share = solution.moves.len() as f64 / game.allowed_clicks.max(1) as f64;
+
+            if target.contains(&share) {
+                return game;
+            }
+
+            let distance = if share < *target.start() {
+                cohesion -= COHESION_STEP;
+                target.start() - share
+            } else {
+                cohesion += COHESION_STEP;
+                share - target.end()
+            };
+            cohesion = cohesion.clamp(-1.0, 1.0);
+
+            if closest
+                .as_ref()
+                .is_none_or(|(closest_distance, _)| distance < *closest_distance)
+            {
+                closest = Some((distance, game));
+            }
+        }
+
+        closest.unwrap().1
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Seeded, since any board may turn out easier or harder than intended by chance.
+    fn settings(difficulty: Difficulty, seed: u64) -> Settings {
+        Settings {
+            size: 8,
+            number_of_colours: 4,
+            difficulty: Some(difficulty),
+            seed: Some(seed),
+            ..Settings::default()
+        }
+    }
+
+    fn average_moves(difficulty: Difficulty) -> f64 {
+        let total: usize = (0..5)
+            .map(|seed| {
+                let game = Game::create(&settings(difficulty, seed));
+                solver::solve(&game.graph, &game.start, ESTIMATE_LIMIT)
+                    .moves
+                    .len()
+            })
+            .sum();
+
+        total as f64 / 5.0
+    }
+
+    #[test]
+    fn should_need_more_moves_on_harder_boards() {
+        assert!(average_moves(Difficulty::Easy) < average_moves(Difficulty::Hard));
+    }
+
+    #[test]
+    fn should_be_possible_to_flood_hard_boards_within_budget() {
+        let game = Game::create(&settings(Difficulty::Hard, 0));
+        let solution = solver::solve(&game.graph, &game.start, ESTIMATE_LIMIT);
+
+        assert!(solution.moves.len() as u32 <= game.allowed_clicks);
+    }
+}
@@ -1,42 +1,325 @@
-use crate::colour::Colour;
-use crate::graph::{Graph, Position};
+use std::collections::BTreeSet;
+use std::rc::Rc;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::colour::{Colour, Palette};
+use crate::difficulty::Difficulty;
+use crate::generator::{self, BoardGenerator, GeneratorKind};
+use crate::graph::{Graph, Lock, Position};
 use crate::grid::Grid;
+use crate::mask::Mask;
+use crate::picture::{Dithering, Picture};
+use crate::topology::{Connectivity, Topology, TopologyKind};
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum GameState {
     Solving,
     Solved,
+    TimeUp,
+}
+
+/// What kind of boards to generate.
+#[derive(Clone, Debug)]
+pub struct Settings {
+    /// Height and width of the grid in cells, unless there is a mask.
+    pub size: u32,
+
+    /// The number of different colours that can be used for the cells.
+    pub number_of_colours: u32,
+
+    pub topology: TopologyKind,
+
+    pub connectivity: Connectivity,
+
+    /// The shape of the board, which also determines its height and width.
+    pub mask: Option<Rc<Mask>>,
+
+    /// The number of cells which resist being flooded.
+    pub locked_cells: usize,
+
+    /// What it takes to unlock each of the locked cells.
+    pub lock: Lock,
+
+    /// How hard boards should be, if they should not just be random.
+    pub difficulty: Option<Difficulty>,
+
+    /// How to colour the cells of new boards.
+    pub generator: GeneratorKind,
+
+    /// Generate the same board every time.
+    pub seed: Option<u64>,
+
+    /// Every colour covers at least this many cells, as far as the board is large enough.
+    pub minimum_cells_per_colour: usize,
+
+    /// Show this picture on the board instead of generating random boards.
+    pub picture: Option<Rc<Picture>>,
+
+    /// How to approximate the colours of `picture`.
+    pub dithering: Dithering,
+
+    /// What the colours look like.
+    pub palette: Rc<Palette>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            size: 14,
+            number_of_colours: 6,
+            topology: TopologyKind::Square,
+            connectivity: Connectivity::Edges,
+            mask: None,
+            locked_cells: 0,
+            lock: Lock::Matches(2),
+            difficulty: None,
+            generator: GeneratorKind::Uniform,
+            seed: None,
+            minimum_cells_per_colour: 1,
+            picture: None,
+            dithering: Dithering::None,
+            palette: Rc::new(Palette::default()),
+        }
+    }
 }
 
 pub struct Game {
     pub state: GameState,
     pub graph: Graph,
+    /// The shape of the board.
+    pub topology: Rc<dyn Topology>,
+    pub connectivity: Connectivity,
+    pub mask: Option<Rc<Mask>>,
+    /// The colours on the board when it was generated, in the order of the palette.
+    pub colours: Vec<Colour>,
+    /// The cell whose component is flooded, i.e. the top left one unless it is void.
+    pub start: Position,
     pub number_of_clicks: u32,
     pub allowed_clicks: u32,
+    /// The fewest moves that flood the board, if known.
+    pub optimum: Option<u32>,
+    /// What the mode made of the game once it is over, e.g. whether it was won.
+    pub verdict: Option<String>,
 }
 
-const TOP_LEFT_CELL: Position = Position {
+/// How many boards to try before settling for one that falls short of what was asked for, e.g.
+/// one where the flood starts out too large.
+pub const MAXIMUM_ATTEMPTS: usize = 20;
+
+/// Boards where the flood starts out covering more than this share of the cells are rejected.
+const MAXIMUM_START_SHARE: f64 = 0.25;
+
+pub const TOP_LEFT_CELL: Position = Position {
     column: 0_usize,
     row: 0_usize,
 };
 
+/// The number of moves a player may use on a grid of the given size.
+pub fn allowed_clicks(
+    size: u32,
+    number_of_colours: u32,
+    topology: TopologyKind,
+    connectivity: Connectivity,
+) -> u32 {
+    let mut allowed_clicks = 25 * 2 * size * number_of_colours / (2 * 14 * 6);
+
+    // Triangles have fewer neighbours than squares, so that flooding them takes about half as
+    // many moves again.
+    if topology == TopologyKind::Triangular {
+        allowed_clicks = allowed_clicks * 3 / 2;
+    }
+
+    // Hexagons have more neighbours than squares, so that flooding them takes about a quarter
+    // fewer moves.
+    if topology == TopologyKind::Hexagonal {
+        allowed_clicks = allowed_clicks * 3 / 4;
+    }
+
+    match connectivity {
+        Connectivity::Edges => allowed_clicks,
+        // Components grow a lot faster when corners count, so that boards can be flooded in
+        // about 40% fewer moves.
+        Connectivity::EdgesAndCorners => allowed_clicks * 3 / 5,
+    }
+}
+
+impl Settings {
+    /// The source of randomness for new boards, which yields the same boards every time if there
+    /// is a seed.
+    pub fn rng(&self) -> StdRng {
+        match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        }
+    }
+}
+
 impl Game {
-    pub fn create(size: u32, number_of_colours: u32) -> Self {
-        let grid = Grid::generate(size as usize, number_of_colours);
-        let graph = Graph::create(&grid);
+    pub fn create(settings: &Settings) -> Self {
+        Self::create_with(settings, &mut settings.rng())
+    }
 
-        let allowed_clicks = 25 * 2 * size * number_of_colours / (2 * 14 * 6);
+    /// Create a board taking all randomness from `rng`, e.g. to generate several different boards
+    /// from one seed.
+    pub fn create_with(settings: &Settings, rng: &mut StdRng) -> Self {
+        match settings.difficulty {
+            Some(difficulty) => difficulty.create_game(settings, rng),
+            None => Self::generate(settings, &*settings.generator.create(), rng),
+        }
+    }
+
+    /// Generate a board according to `settings`, colouring it with `generator`.
+    pub fn generate(settings: &Settings, generator: &dyn BoardGenerator, rng: &mut StdRng) -> Self {
+        let (topology, size) = match &settings.mask {
+            Some(mask) => (
+                settings
+                    .topology
+                    .create(mask.number_of_rows(), mask.number_of_columns()),
+                // The budget of a shaped board is that of a square one with as many cells.
+                (mask.number_of_cells() as f64).sqrt().ceil() as u32,
+            ),
+            None => {
+                let size = settings.size as usize;
+                (settings.topology.create(size, size), settings.size)
+            }
+        };
+
+        let mut allowed_clicks = allowed_clicks(
+            size,
+            settings.number_of_colours,
+            settings.topology,
+            settings.connectivity,
+        );
+        // Reaching a locked cell with its colour again may take an extra move.
+        if let Lock::Matches(matches) = settings.lock {
+            allowed_clicks += (matches - 1) * settings.locked_cells as u32;
+        }
+
+        if let Some(picture) = &settings.picture {
+            let grid = picture
+                .grid(
+                    topology,
+                    &settings.palette,
+                    settings.number_of_colours,
+                    settings.dithering,
+                )
+                .with_connectivity(settings.connectivity)
+                .with_mask(settings.mask.clone())
+                .with_random_locks(settings.locked_cells, settings.lock, rng);
+            return Self::from_grid(&grid, allowed_clicks);
+        }
+
+        let palette = generator::palette(settings.number_of_colours);
+        let mut attempts = 0;
+        let grid = loop {
+            let mut grid =
+                Grid::generate_with(topology.clone(), settings.number_of_colours, generator, rng)
+                    .with_connectivity(settings.connectivity)
+                    .with_mask(settings.mask.clone());
+            grid.ensure_colour_counts(
+                &palette,
+                settings.minimum_cells_per_colour,
+                generator.is_mirrored(),
+                rng,
+            );
+            attempts += 1;
+
+            let number_of_cells = (0..grid.cells.len())
+                .filter(|&cell| !grid.is_void(cell))
+                .count();
+            let start = grid.position(grid.first_cell());
+            let start_cells = Graph::create(&grid).find_component(&start).cells.len();
+            if attempts == MAXIMUM_ATTEMPTS
+                || start_cells as f64 <= MAXIMUM_START_SHARE * number_of_cells as f64
+            {
+                break grid.with_random_locks(settings.locked_cells, settings.lock, rng);
+            }
+        };
+
+        Self::from_grid(&grid, allowed_clicks)
+    }
+
+    pub fn from_grid(grid: &Grid, allowed_clicks: u32) -> Self {
+        let graph = Graph::create(grid);
+        let colours: BTreeSet<Colour> = (0..grid.cells.len())
+            .filter(|&cell| !grid.is_void(cell))
+            .map(|cell| grid.cells[cell])
+            .collect();
 
         Self {
             state: GameState::Solving,
             graph,
+            topology: grid.topology.clone(),
+            connectivity: grid.connectivity,
+            mask: grid.mask.clone(),
+            colours: colours.into_iter().collect(),
+            start: grid.position(grid.first_cell()),
             number_of_clicks: 0,
             allowed_clicks,
+            optimum: None,
+            verdict: None,
         }
     }
 
+    /// The current colours of all cells.
+    pub fn grid(&self) -> Grid {
+        self.graph
+            .to_grid(self.topology.clone())
+            .with_connectivity(self.connectivity)
+            .with_mask(self.mask.clone())
+    }
+
     pub fn fill_component_of_top_left_cell_with(&mut self, colour: Colour) {
         self.number_of_clicks += 1;
         self.graph
-            .change_colour_of_component_at(&TOP_LEFT_CELL, colour);
+            .change_colour_of_component_at(&self.start, colour);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_use_every_colour_on_small_board() {
+        let settings = Settings {
+            size: 3,
+            number_of_colours: 8,
+            ..Settings::default()
+        };
+
+        for _ in 0..10 {
+            let game = Game::create(&settings);
+
+            assert_eq!(game.colours, generator::palette(8));
+        }
+    }
+
+    #[test]
+    fn should_not_start_with_large_flood() {
+        // Seeded, since any generator may fail to avoid a large flood by chance.
+        for seed in 0..10 {
+            let settings = Settings {
+                size: 6,
+                number_of_colours: 3,
+                generator: GeneratorKind::Clusters,
+                seed: Some(seed),
+                ..Settings::default()
+            };
+            let game = Game::create(&settings);
+
+            let flood = game.graph.find_component(&game.start);
+            assert!(flood.cells.len() <= 9);
+        }
+    }
+
+    #[test]
+    fn should_allow_fewer_moves_the_more_neighbours_cells_have() {
+        let budget = |topology| allowed_clicks(14, 6, topology, Connectivity::Edges);
+
+        assert!(budget(TopologyKind::Hexagonal) < budget(TopologyKind::Square));
+        assert!(budget(TopologyKind::Square) < budget(TopologyKind::Triangular));
     }
 }
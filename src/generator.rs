@@ -0,0 +1,332 @@
+use std::fmt::Debug;
+
+use rand::distributions::WeightedIndex;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+
+use crate::colour::Colour;
+use crate::topology::Topology;
+
+/// The average number of cells per blob of `Clusters`.
+const CELLS_PER_CLUSTER: usize = 6;
+
+/// How likely a cell of `Stripes` takes the colour of its row.
+const STRIPE_PROBABILITY: f64 = 0.6;
+
+/// A strategy for colouring the cells of a new board.  Generators take all their randomness from
+/// `rng`, so the same seed always yields the same board.
+pub trait BoardGenerator: Debug {
+    /// The colour of every cell of `topology`, using only the colours in `palette`.
+    fn generate(
+        &self,
+        topology: &dyn Topology,
+        palette: &[Colour],
+        rng: &mut StdRng,
+    ) -> Vec<Colour>;
+
+    /// Whether the right half of every board mirrors the left half, which recolouring cells
+    /// afterwards has to preserve.
+    fn is_mirrored(&self) -> bool {
+        false
+    }
+}
+
+/// The first `number_of_colours` colours of the palette.
+pub fn palette(number_of_colours: u32) -> Vec<Colour> {
+    (0..number_of_colours as usize).map(Colour).collect()
+}
+
+fn random_colour(palette: &[Colour], rng: &mut StdRng) -> Colour {
+    palette[rng.gen_range(0..palette.len())]
+}
+
+/// The kinds of generators that can be chosen from the command line.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GeneratorKind {
+    Uniform,
+    Clusters,
+    Symmetric,
+    Stripes,
+    Biased,
+}
+
+impl GeneratorKind {
+    pub const NAMES: [&'static str; 5] = ["uniform", "clusters", "symmetric", "stripes", "biased"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "uniform" => Some(GeneratorKind::Uniform),
+            "clusters" => Some(GeneratorKind::Clusters),
+            "symmetric" => Some(GeneratorKind::Symmetric),
+            "stripes" => Some(GeneratorKind::Stripes),
+            "biased" => Some(GeneratorKind::Biased),
+            _ => None,
+        }
+    }
+
+    pub fn create(self) -> Box<dyn BoardGenerator> {
+        match self {
+            GeneratorKind::Uniform => Box::new(Uniform),
+            GeneratorKind::Clusters => Box::new(Clusters),
+            GeneratorKind::Symmetric => Box::new(Symmetric),
+            GeneratorKind::Stripes => Box::new(Stripes),
+            GeneratorKind::Biased => Box::new(Biased),
+        }
+    }
+}
+
+/// Every colour is equally likely for every cell.
+#[derive(Debug)]
+pub struct Uniform;
+
+impl BoardGenerator for Uniform {
+    fn generate(
+        &self,
+        topology: &dyn Topology,
+        palette: &[Colour],
+        rng: &mut StdRng,
+    ) -> Vec<Colour> {
+        (0..topology.number_of_cells())
+            .map(|_| random_colour(palette, rng))
+            .collect()
+    }
+}
+
+/// Cells tend to share the colour of their neighbours (for positive `cohesion`) or to differ from
+/// it (for negative `cohesion`).  `cohesion` is the probability of doing so instead of picking a
+/// colour at random and lies between -1 and 1.
+#[derive(Debug)]
+pub struct Cohesive {
+    pub cohesion: f64,
+}
+
+impl BoardGenerator for Cohesive {
+    fn generate(
+        &self,
+        topology: &dyn Topology,
+        palette: &[Colour],
+        rng: &mut StdRng,
+    ) -> Vec<Colour> {
+        let mut cells: Vec<Colour> = Vec::with_capacity(topology.number_of_cells());
+
+        for cell in 0..topology.number_of_cells() {
+            // Only the neighbours generated before `cell` have a colour yet.
+            let neighbour_colours: Vec<Colour> = topology
+                .neighbours(cell)
+                .into_iter()
+                .filter(|&neighbour| neighbour < cell)
+                .map(|neighbour| cells[neighbour])
+                .collect();
+            let other_colours: Vec<Colour> = palette
+                .iter()
+                .copied()
+                .filter(|colour| !neighbour_colours.contains(colour))
+                .collect();
+
+            let colour = if rng.gen_bool(self.cohesion.abs()) {
+                if self.cohesion > 0.0 {
+                    neighbour_colours.choose(rng)
+                } else {
+                    other_colours.choose(rng)
+                }
+            } else {
+                None
+            };
+            cells.push(
+                colour
+                    .copied()
+                    .unwrap_or_else(|| random_colour(palette, rng)),
+            );
+        }
+
+        cells
+    }
+}
+
+/// Blobs of colour grown from randomly placed seeds, one cell at a time.
+#[derive(Debug)]
+pub struct Clusters;
+
+impl BoardGenerator for Clusters {
+    fn generate(
+        &self,
+        topology: &dyn Topology,
+        palette: &[Colour],
+        rng: &mut StdRng,
+    ) -> Vec<Colour> {
+        let number_of_cells = topology.number_of_cells();
+        let mut cells: Vec<Option<Colour>> = vec![None; number_of_cells];
+
+        let number_of_seeds = (number_of_cells / CELLS_PER_CLUSTER).max(1);
+        let mut frontier =
+            rand::seq::index::sample(rng, number_of_cells, number_of_seeds).into_vec();
+        for &cell in &frontier {
+            cells[cell] = Some(random_colour(palette, rng));
+        }
+
+        // Grow a random blob by the cells around one of its cells.
+        while !frontier.is_empty() {
+            let cell = frontier.swap_remove(rng.gen_range(0..frontier.len()));
+            for neighbour in topology.neighbours(cell) {
+                if cells[neighbour].is_none() {
+                    cells[neighbour] = cells[cell];
+                    frontier.push(neighbour);
+                }
+            }
+        }
+
+        // Cells no blob could reach get a colour of their own.
+        cells
+            .into_iter()
+            .map(|colour| colour.unwrap_or_else(|| random_colour(palette, rng)))
+            .collect()
+    }
+}
+
+/// The right half of the board mirrors the left half.  Cells are mirrored by column, so this only
+/// matches the geometry of boards of square cells, where every row is a mirror image of itself.
+#[derive(Debug)]
+pub struct Symmetric;
+
+impl BoardGenerator for Symmetric {
+    fn generate(
+        &self,
+        topology: &dyn Topology,
+        palette: &[Colour],
+        rng: &mut StdRng,
+    ) -> Vec<Colour> {
+        let columns = topology.number_of_columns();
+        let mut cells: Vec<Colour> = Vec::with_capacity(topology.number_of_cells());
+
+        for cell in 0..topology.number_of_cells() {
+            let column = cell % columns;
+            let mirrored = columns - 1 - column;
+            if mirrored < column {
+                cells.push(cells[cell - column + mirrored]);
+            } else {
+                cells.push(random_colour(palette, rng));
+            }
+        }
+
+        cells
+    }
+
+    fn is_mirrored(&self) -> bool {
+        true
+    }
+}
+
+/// Every row has a colour most of its cells share.
+#[derive(Debug)]
+pub struct Stripes;
+
+impl BoardGenerator for Stripes {
+    fn generate(
+        &self,
+        topology: &dyn Topology,
+        palette: &[Colour],
+        rng: &mut StdRng,
+    ) -> Vec<Colour> {
+        let columns = topology.number_of_columns();
+        let mut stripe = palette[0];
+
+        (0..topology.number_of_cells())
+            .map(|cell| {
+                if cell % columns == 0 {
+                    stripe = random_colour(palette, rng);
+                }
+                if rng.gen_bool(STRIPE_PROBABILITY) {
+                    stripe
+                } else {
+                    random_colour(palette, rng)
+                }
+            })
+            .collect()
+    }
+}
+
+/// The first colour is a lot more common than any other.
+#[derive(Debug)]
+pub struct Biased;
+
+impl BoardGenerator for Biased {
+    fn generate(
+        &self,
+        topology: &dyn Topology,
+        palette: &[Colour],
+        rng: &mut StdRng,
+    ) -> Vec<Colour> {
+        // The first colour is as common as all others taken together would be on a uniform board.
+        let weights = (0..palette.len()).map(|i| if i == 0 { palette.len() - 1 } else { 1 });
+        let distribution = WeightedIndex::new(weights).unwrap();
+
+        (0..topology.number_of_cells())
+            .map(|_| palette[distribution.sample(rng)])
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::topology::TopologyKind;
+
+    fn generate(generator: &dyn BoardGenerator, seed: u64) -> Vec<Colour> {
+        let topology = TopologyKind::Square.create(6, 7);
+        generator.generate(&*topology, &palette(5), &mut StdRng::seed_from_u64(seed))
+    }
+
+    #[test]
+    fn should_generate_same_board_from_same_seed() {
+        for name in GeneratorKind::NAMES {
+            let generator = GeneratorKind::from_name(name).unwrap().create();
+
+            assert_eq!(generate(&*generator, 3), generate(&*generator, 3));
+            assert_eq!(generate(&*generator, 3).len(), 42);
+        }
+    }
+
+    #[test]
+    fn should_mirror_left_half_of_symmetric_board() {
+        let cells = generate(&Symmetric, 1);
+
+        for row in 0..6 {
+            for column in 0..7 {
+                assert_eq!(cells[row * 7 + column], cells[row * 7 + 6 - column]);
+            }
+        }
+    }
+
+    #[test]
+    fn should_colour_board_with_full_cohesion_in_one_colour() {
+        let cells = generate(&Cohesive { cohesion: 1.0 }, 2);
+
+        assert!(cells.iter().all(|&colour| colour == cells[0]));
+    }
+
+    #[test]
+    fn should_not_give_neighbours_same_colour_with_negative_cohesion() {
+        let topology = TopologyKind::Square.create(4, 4);
+        let cells = Cohesive { cohesion: -1.0 }.generate(
+            &*topology,
+            &palette(3),
+            &mut StdRng::seed_from_u64(0),
+        );
+
+        for cell in 0..cells.len() {
+            for neighbour in topology.neighbours(cell) {
+                assert_ne!(cells[cell], cells[neighbour]);
+            }
+        }
+    }
+
+    #[test]
+    fn should_make_first_colour_most_common_with_bias() {
+        let cells = generate(&Biased, 4);
+        let count = |colour| cells.iter().filter(|&&cell| cell == colour).count();
+
+        assert!(palette(5)[1..]
+            .iter()
+            .all(|&colour| count(palette(5)[0]) > count(colour)));
+    }
+}
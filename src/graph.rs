@@ -1,12 +1,14 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 use std::hash::Hash;
+use std::rc::Rc;
 
 use crate::colour::Colour;
 use crate::grid::Grid;
+use crate::topology::Topology;
 
-// Represent the state of the game as a graph.  One node for each cell.  There is an edge between
-// two cells if and only if they are the same colour and have a distance of 1 according to the
-// Manhattan metric.
+// Represent the state of the game as a graph.  One node for each cell that is not void.  There is
+// an edge between two cells if and only if they are the same colour and neighbours according to
+// the topology of the board, which with corners connected includes cells touching at a corner.
 //
 // The game has only one real operation (i.e. not counting level generation): Switching the colour
 // of the connected component containing the top left cell to whichever colour the clicked cell
@@ -29,11 +31,24 @@ pub struct Position {
     pub column: usize,
 }
 
+/// What it takes for a locked component to join the flood.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Lock {
+    /// The component opens up once this many more moves have been made.
+    Moves(u32),
+
+    /// The component joins the flood once it has been reached with its colour this many more
+    /// times.
+    Matches(u32),
+}
+
 #[derive(Debug, Clone)]
 pub struct ConnectedComponent {
-    id: usize,
+    pub id: usize,
     pub colour: Colour,
     pub cells: HashSet<Position>,
+    /// Locked components resist being flooded.
+    pub lock: Option<Lock>,
 }
 
 impl PartialEq for ConnectedComponent {
@@ -57,16 +72,17 @@ pub struct Graph {
 }
 
 fn find_connected_components(grid: &Grid) -> Vec<(ConnectedComponent, HashSet<usize>)> {
-    let rows = grid.number_of_rows;
-    let columns = grid.number_of_columns;
+    let columns = grid.number_of_columns();
 
-    let mut remaining_cells: HashSet<usize> = (0..grid.cells.len()).into_iter().collect();
+    let mut remaining_cells: BTreeSet<usize> = (0..grid.cells.len())
+        .filter(|&cell| !grid.is_void(cell))
+        .collect();
 
     let mut components = vec![];
     let mut counter = 0;
 
-    // Take any element to land in any component.
-    while let Some(&start_cell_index) = remaining_cells.iter().next() {
+    // Take the first remaining cell so the components are numbered in reading order.
+    while let Some(&start_cell_index) = remaining_cells.first() {
         let mut visited = HashSet::new();
         let mut neighbours = HashSet::new();
         let mut queue = VecDeque::new();
@@ -75,29 +91,21 @@ fn find_connected_components(grid: &Grid) -> Vec<(ConnectedComponent, HashSet<us
 
         // Gather all cells in the connected component of start_cell_index.
         while let Some(i) = queue.pop_front() {
-            visited.insert(i);
-            remaining_cells.remove(&i);
-
-            let mut neighbouring_cells = vec![];
-            if i % columns > 0 {
-                neighbouring_cells.push(i - 1);
-            }
-            if i % columns < columns - 1 {
-                neighbouring_cells.push(i + 1);
-            }
-            if i >= columns {
-                neighbouring_cells.push(i - columns)
-            }
-            if i < (rows - 1) * columns {
-                neighbouring_cells.push(i + columns)
+            // A cell may be queued by several of its neighbours before it is visited.
+            if !visited.insert(i) {
+                continue;
             }
+            remaining_cells.remove(&i);
 
-            for neighbour in neighbouring_cells {
+            for neighbour in grid.neighbours(i) {
                 if visited.contains(&neighbour) {
                     continue;
                 }
 
-                if grid.cells[i] == grid.cells[neighbour] {
+                // Locked cells only form components with cells locked the same way.
+                if grid.cells[i] == grid.cells[neighbour]
+                    && grid.locks.get(&i) == grid.locks.get(&neighbour)
+                {
                     queue.push_back(neighbour);
                 } else {
                     neighbours.insert(neighbour);
@@ -115,8 +123,9 @@ fn find_connected_components(grid: &Grid) -> Vec<(ConnectedComponent, HashSet<us
 
         let component = ConnectedComponent {
             id: counter,
-            colour: grid.cells[*visited.iter().next().unwrap()],
+            colour: grid.cells[start_cell_index],
             cells,
+            lock: grid.locks.get(&start_cell_index).copied(),
         };
         counter += 1;
 
@@ -149,8 +158,8 @@ impl Graph {
 
             for cell in neighbour_cells {
                 let position = Position {
-                    column: cell % grid.number_of_columns,
-                    row: cell / grid.number_of_columns,
+                    column: cell % grid.number_of_columns(),
+                    row: cell / grid.number_of_columns(),
                 };
                 neighbour_components.insert(map_cell_to_component[&position].id);
             }
@@ -169,20 +178,24 @@ impl Graph {
         }
     }
 
-    fn to_grid(&self, rows: usize, columns: usize) -> Grid {
-        let mut cells = vec![Colour::Red; rows * columns];
+    /// The colour of every cell, laid out according to `topology`.
+    pub fn to_grid(&self, topology: Rc<dyn Topology>) -> Grid {
+        let columns = topology.number_of_columns();
+        let mut cells = vec![Colour(0); topology.number_of_cells()];
+        let mut locks = BTreeMap::new();
 
         for component in self.neighbours.keys() {
-            for position in &self.components[component].cells {
-                cells[position.column + position.row * columns] = self.components[component].colour;
+            let component = &self.components[component];
+            for position in &component.cells {
+                let cell = position.column + position.row * columns;
+                cells[cell] = component.colour;
+                if let Some(lock) = component.lock {
+                    locks.insert(cell, lock);
+                }
             }
         }
 
-        Grid {
-            number_of_rows: rows,
-            number_of_columns: columns,
-            cells,
-        }
+        Grid::new(topology, cells).with_locks(locks)
     }
 
     pub fn find_component(&self, position: &Position) -> &ConnectedComponent {
@@ -195,6 +208,36 @@ impl Graph {
         unreachable!()
     }
 
+    /// The components which would join the component at `position` if it changed to `colour`,
+    /// i.e. its neighbours of that colour along with their neighbours of that colour and so on,
+    /// as far as no lock keeps them out.  The window previews moves with it.
+    #[cfg(any(feature = "gui", test))]
+    pub fn captured_by(&self, position: &Position, colour: Colour) -> Vec<&ConnectedComponent> {
+        let component_id = self.find_component(position).id;
+        let mut captured = vec![];
+        let mut reached = HashSet::new();
+        let mut queue: Vec<usize> = self.neighbours[&component_id].iter().copied().collect();
+
+        while let Some(id) = queue.pop() {
+            if id == component_id || !reached.insert(id) {
+                continue;
+            }
+
+            let component = &self.components[&id];
+            // A lock with one move or match left opens up during the move.
+            let joins = match component.lock {
+                None => true,
+                Some(Lock::Moves(count) | Lock::Matches(count)) => count <= 1,
+            };
+            if component.colour == colour && joins {
+                captured.push(component);
+                queue.extend(&self.neighbours[&id]);
+            }
+        }
+
+        captured
+    }
+
     pub fn change_colour_of_component_at(&mut self, position: &Position, colour: Colour) {
         let component_id = self.find_component(position).id;
 
@@ -210,18 +253,40 @@ impl Graph {
         let mut new_neighbours = HashSet::new();
         let mut removed_neighbours = HashSet::new();
 
-        for id in &neighbours {
-            if *id != component_id && self.components[id].colour == colour {
-                let neighbour_component = self.components.remove(id).unwrap();
-                new_cells.extend(neighbour_component.cells.into_iter());
-                let set_of_neighbour_ids = self.neighbours.remove(&neighbour_component.id).unwrap();
-                new_neighbours.extend(set_of_neighbour_ids.into_iter());
-                removed_neighbours.insert(*id);
+        // Every move brings components locked for a number of moves closer to opening up.
+        for other in self.components.values_mut() {
+            if let Some(Lock::Moves(moves)) = other.lock {
+                other.lock = (moves > 1).then_some(Lock::Moves(moves - 1));
+            }
+        }
+
+        // Components which have just been unlocked may touch other components of the same colour,
+        // so keep going until no more neighbours of the new colour can be merged.
+        let mut reached = HashSet::new();
+        let mut queue: Vec<usize> = neighbours.iter().copied().collect();
+        while let Some(id) = queue.pop() {
+            if id == component_id || !reached.insert(id) || self.components[&id].colour != colour {
+                continue;
             }
+
+            let neighbour_component = self.components.get_mut(&id).unwrap();
+            if let Some(Lock::Matches(matches)) = neighbour_component.lock {
+                neighbour_component.lock = (matches > 1).then_some(Lock::Matches(matches - 1));
+            }
+            if neighbour_component.lock.is_some() {
+                continue;
+            }
+
+            let neighbour_component = self.components.remove(&id).unwrap();
+            new_cells.extend(neighbour_component.cells);
+            let set_of_neighbour_ids = self.neighbours.remove(&neighbour_component.id).unwrap();
+            queue.extend(&set_of_neighbour_ids);
+            new_neighbours.extend(set_of_neighbour_ids);
+            removed_neighbours.insert(id);
         }
 
         // Add new neighbours
-        neighbours.extend(new_neighbours.clone().into_iter());
+        neighbours.extend(new_neighbours.clone());
         // Remove the ID of the component itself
         neighbours.remove(&component_id);
         // and the IDs of the neighbours that were merged into the component
@@ -231,7 +296,7 @@ impl Graph {
             .collect();
 
         // Add the cells from the neighbours which now have the new colour
-        component.cells.extend(new_cells.into_iter());
+        component.cells.extend(new_cells);
         // and update the colour.  With this, the component itself is done.
         component.colour = colour;
 
@@ -241,7 +306,9 @@ impl Graph {
             for incorporated_neighbour_id in &removed_neighbours {
                 n.remove(incorporated_neighbour_id);
             }
-            n.insert(component_id);
+        }
+        for id in &neighbours {
+            self.neighbours.get_mut(id).unwrap().insert(component_id);
         }
 
         // Put the component and neighbours back into the Graph
@@ -253,6 +320,9 @@ impl Graph {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::game::TOP_LEFT_CELL;
+    use crate::mask::Mask;
+    use crate::topology::{Connectivity, TopologyKind};
 
     #[test]
     fn should_return_same_grid_as_input() {
@@ -261,18 +331,14 @@ mod test {
         let grid = Grid::generate(size, number_of_colours);
 
         let graph = Graph::create(&grid);
-        let reconstituted_grid = graph.to_grid(size, size);
+        let reconstituted_grid = graph.to_grid(grid.topology.clone());
 
         assert_eq!(reconstituted_grid, grid);
     }
 
     #[test]
     fn should_have_one_component() {
-        let grid = Grid {
-            number_of_columns: 2,
-            number_of_rows: 2,
-            cells: vec![Colour::Red; 4],
-        };
+        let grid = Grid::new(TopologyKind::Square.create(2, 2), vec![Colour::RED; 4]);
 
         let graph = Graph::create(&grid);
 
@@ -281,11 +347,10 @@ mod test {
 
     #[test]
     fn should_have_four_component() {
-        let grid = Grid {
-            number_of_columns: 2,
-            number_of_rows: 2,
-            cells: vec![Colour::Red, Colour::Yellow, Colour::Yellow, Colour::Red],
-        };
+        let grid = Grid::new(
+            TopologyKind::Square.create(2, 2),
+            vec![Colour::RED, Colour::YELLOW, Colour::YELLOW, Colour::RED],
+        );
 
         let graph = Graph::create(&grid);
 
@@ -294,15 +359,100 @@ mod test {
 
     #[test]
     fn should_have_two_component() {
-        let grid = Grid {
-            number_of_columns: 2,
-            number_of_rows: 2,
-            cells: vec![Colour::Red, Colour::Red, Colour::Yellow, Colour::Yellow],
-        };
+        let grid = Grid::new(
+            TopologyKind::Square.create(2, 2),
+            vec![Colour::RED, Colour::RED, Colour::YELLOW, Colour::YELLOW],
+        );
+
+        let graph = Graph::create(&grid);
+
+        assert_eq!(graph.neighbours.len(), 2);
+    }
+
+    #[test]
+    fn should_connect_cells_touching_at_corners_with_eight_neighbours() {
+        let grid = Grid::new(
+            TopologyKind::Square.create(2, 2),
+            vec![Colour::RED, Colour::YELLOW, Colour::YELLOW, Colour::RED],
+        )
+        .with_connectivity(Connectivity::EdgesAndCorners);
+
+        let graph = Graph::create(&grid);
+
+        assert_eq!(graph.neighbours.len(), 2);
+    }
+
+    #[test]
+    fn should_leave_out_void_cells() {
+        let grid = Grid::new(TopologyKind::Square.create(1, 3), vec![Colour::RED; 3])
+            .with_mask(Some(Rc::new(Mask::parse(".#.").unwrap())));
 
         let graph = Graph::create(&grid);
 
         assert_eq!(graph.neighbours.len(), 2);
+        assert!(graph.neighbours.values().all(HashSet::is_empty));
+    }
+
+    fn locked_grid(lock: Lock) -> Grid {
+        Grid::new(
+            TopologyKind::Square.create(1, 3),
+            vec![Colour::RED, Colour::YELLOW, Colour::YELLOW],
+        )
+        .with_locks(BTreeMap::from([(1, lock)]))
+    }
+
+    #[test]
+    fn should_join_locked_component_when_reached_with_its_colour_twice() {
+        let mut graph = Graph::create(&locked_grid(Lock::Matches(2)));
+        assert_eq!(graph.components.len(), 3);
+
+        graph.change_colour_of_component_at(&TOP_LEFT_CELL, Colour::YELLOW);
+        assert_eq!(graph.components.len(), 3);
+        assert_eq!(graph.components[&1].lock, Some(Lock::Matches(1)));
+
+        graph.change_colour_of_component_at(&TOP_LEFT_CELL, Colour::YELLOW);
+        assert_eq!(graph.components.len(), 1);
+    }
+
+    #[test]
+    fn should_unlock_component_after_number_of_moves() {
+        let mut graph = Graph::create(&locked_grid(Lock::Moves(2)));
+
+        graph.change_colour_of_component_at(&TOP_LEFT_CELL, Colour::YELLOW);
+        assert_eq!(graph.components.len(), 3);
+
+        graph.change_colour_of_component_at(&TOP_LEFT_CELL, Colour::GREEN);
+        graph.change_colour_of_component_at(&TOP_LEFT_CELL, Colour::YELLOW);
+        assert_eq!(graph.components.len(), 1);
+        assert!(graph.components.values().all(|c| c.lock.is_none()));
+    }
+
+    #[test]
+    fn should_capture_as_many_cells_as_a_move_adds() {
+        let grid = Grid::generate(8, 4);
+
+        for colour in crate::generator::palette(4) {
+            let mut graph = Graph::create(&grid);
+            let before = graph.find_component(&TOP_LEFT_CELL).cells.len();
+            let captured: usize = graph
+                .captured_by(&TOP_LEFT_CELL, colour)
+                .iter()
+                .map(|component| component.cells.len())
+                .sum();
+
+            graph.change_colour_of_component_at(&TOP_LEFT_CELL, colour);
+            let after = graph.find_component(&TOP_LEFT_CELL).cells.len();
+            assert_eq!(after - before, captured);
+        }
+    }
+
+    #[test]
+    fn should_not_capture_locked_component_before_last_match() {
+        let mut graph = Graph::create(&locked_grid(Lock::Matches(2)));
+        assert!(graph.captured_by(&TOP_LEFT_CELL, Colour::YELLOW).is_empty());
+
+        graph.change_colour_of_component_at(&TOP_LEFT_CELL, Colour::YELLOW);
+        assert_eq!(graph.captured_by(&TOP_LEFT_CELL, Colour::YELLOW).len(), 2);
     }
 
     #[test]
@@ -335,6 +485,24 @@ mod test {
         }
     }
 
+    #[test]
+    fn should_keep_neighbour_relation_symmetric_when_changing_colour() {
+        let size = 6;
+        let number_of_colours = 4;
+        let grid = Grid::generate(size, number_of_colours);
+        let top_left_cell = Position { row: 0, column: 0 };
+
+        let mut graph = Graph::create(&grid);
+        graph.change_colour_of_component_at(&top_left_cell, Colour::RED);
+        graph.change_colour_of_component_at(&top_left_cell, Colour::YELLOW);
+
+        for (id, neighbours) in &graph.neighbours {
+            for neighbour in neighbours {
+                assert!(graph.neighbours[neighbour].contains(id));
+            }
+        }
+    }
+
     #[test]
     fn should_contain_only_component_ids_as_values_in_neighbours_map() {
         let size = 4;
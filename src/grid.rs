@@ -1,30 +1,228 @@
-use macroquad::rand::gen_range;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::rc::Rc;
+
+use rand::rngs::StdRng;
+use rand::seq::{IteratorRandom, SliceRandom};
+use rand::SeedableRng;
 
 use crate::colour::*;
+use crate::generator::{self, BoardGenerator, Uniform};
+use crate::graph::{Lock, Position};
+use crate::mask::Mask;
+use crate::topology::{Connectivity, Topology, TopologyKind};
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug)]
 pub struct Grid {
-    pub number_of_rows: usize,
-    pub number_of_columns: usize,
+    pub topology: Rc<dyn Topology>,
+
+    /// Whether cells touching at a corner belong to the same component if they have the same
+    /// colour.
+    pub connectivity: Connectivity,
+
+    /// Which cells are void, if the board is not a full rectangle.
+    pub mask: Option<Rc<Mask>>,
 
+    /// The colour of every cell.  The colours of void cells are meaningless.
     pub cells: Vec<Colour>,
+
+    /// The locked cells and what it takes to unlock them.
+    pub locks: BTreeMap<usize, Lock>,
+}
+
+impl PartialEq for Grid {
+    fn eq(&self, other: &Self) -> bool {
+        self.topology.name() == other.topology.name()
+            && self.connectivity == other.connectivity
+            && self.mask == other.mask
+            && self.number_of_rows() == other.number_of_rows()
+            && self.number_of_columns() == other.number_of_columns()
+            && self.cells == other.cells
+            && self.locks == other.locks
+    }
 }
 
+impl Eq for Grid {}
+
 impl Grid {
-    pub fn generate(size: usize, number_of_colours: u32) -> Self {
-        let mut cells = vec![Colour::Red; size * size];
-        for i in 0..size {
-            for j in 0..size {
-                cells[size * i + j] =
-                    ALL_COLOURS[gen_range(0, ALL_COLOURS.len().min(number_of_colours as usize))];
-            }
-        }
+    pub fn new(topology: Rc<dyn Topology>, cells: Vec<Colour>) -> Self {
+        assert_eq!(topology.number_of_cells(), cells.len());
 
         Self {
-            number_of_columns: size,
-            number_of_rows: size,
+            topology,
+            connectivity: Connectivity::Edges,
+            mask: None,
             cells,
+            locks: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_connectivity(self, connectivity: Connectivity) -> Self {
+        Self {
+            connectivity,
+            ..self
+        }
+    }
+
+    pub fn with_mask(self, mask: Option<Rc<Mask>>) -> Self {
+        if let Some(mask) = &mask {
+            assert_eq!(mask.number_of_rows(), self.number_of_rows());
+            assert_eq!(mask.number_of_columns(), self.number_of_columns());
+        }
+
+        Self { mask, ..self }
+    }
+
+    pub fn with_locks(self, locks: BTreeMap<usize, Lock>) -> Self {
+        Self { locks, ..self }
+    }
+
+    /// Lock `count` cells chosen at random, leaving out void cells and the cell the flood starts
+    /// from.
+    pub fn with_random_locks(self, count: usize, lock: Lock, rng: &mut StdRng) -> Self {
+        let first_cell = self.first_cell();
+        let locks = (0..self.cells.len())
+            .filter(|&cell| cell != first_cell && !self.is_void(cell))
+            .choose_multiple(rng, count)
+            .into_iter()
+            .map(|cell| (cell, lock))
+            .collect();
+
+        self.with_locks(locks)
+    }
+
+    pub fn is_void(&self, cell: usize) -> bool {
+        self.mask.as_ref().is_some_and(|mask| mask.is_void(cell))
+    }
+
+    /// The cells adjacent to `cell` according to the connectivity of the grid.  Void cells are
+    /// nobody's neighbours.
+    pub fn neighbours(&self, cell: usize) -> Vec<usize> {
+        let mut neighbours = self.topology.neighbours(cell);
+        if self.connectivity == Connectivity::EdgesAndCorners {
+            neighbours.extend(self.topology.diagonal_neighbours(cell));
+        }
+        neighbours.retain(|&neighbour| !self.is_void(neighbour));
+        neighbours
+    }
+
+    /// The first cell in reading order which is not void.
+    pub fn first_cell(&self) -> usize {
+        (0..self.cells.len())
+            .find(|&cell| !self.is_void(cell))
+            .expect("A grid has at least one cell which is not void")
+    }
+
+    pub fn position(&self, cell: usize) -> Position {
+        Position {
+            column: cell % self.number_of_columns(),
+            row: cell / self.number_of_columns(),
+        }
+    }
+
+    /// Recolour random cells until every colour of `palette` covers at least `minimum` cells, or
+    /// as many as there are cells for.  The cell the flood starts from keeps its colour.  If
+    /// `mirrored`, cells are recoloured together with their mirror image in the other half of
+    /// their row, so that symmetric boards stay symmetric.
+    pub fn ensure_colour_counts(
+        &mut self,
+        palette: &[Colour],
+        minimum: usize,
+        mirrored: bool,
+        rng: &mut StdRng,
+    ) {
+        let first_cell = self.first_cell();
+        let columns = self.number_of_columns();
+        let cells: Vec<usize> = (0..self.cells.len())
+            .filter(|&cell| !self.is_void(cell))
+            .collect();
+        let minimum = minimum.min(cells.len() / palette.len());
+
+        // The cells recoloured together, each pair of mirrored cells once.
+        let groups: Vec<Vec<usize>> = cells
+            .iter()
+            .map(|&cell| {
+                let mirror = cell - cell % columns + columns - 1 - cell % columns;
+                if mirrored && mirror != cell && !self.is_void(mirror) {
+                    vec![cell.min(mirror), cell.max(mirror)]
+                } else {
+                    vec![cell]
+                }
+            })
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .filter(|group| !group.contains(&first_cell))
+            .collect();
+
+        let mut counts: HashMap<Colour, usize> = HashMap::new();
+        for &cell in &cells {
+            *counts.entry(self.cells[cell]).or_default() += 1;
         }
+
+        for &colour in palette {
+            while counts.get(&colour).copied().unwrap_or(0) < minimum {
+                // Take the cells from a colour which can spare them.  Mirrored pairs may leave
+                // no colour able to spare two cells, in which case `colour` stays short.
+                let Some(group) = groups
+                    .iter()
+                    .filter(|group| counts[&self.cells[group[0]]] >= minimum + group.len())
+                    .collect::<Vec<_>>()
+                    .choose(rng)
+                    .copied()
+                else {
+                    debug_assert!(mirrored, "Some colour covers more cells than the minimum");
+                    break;
+                };
+                *counts.get_mut(&self.cells[group[0]]).unwrap() -= group.len();
+                *counts.entry(colour).or_default() += group.len();
+                for &cell in group {
+                    self.cells[cell] = colour;
+                }
+            }
+        }
+    }
+
+    pub fn number_of_rows(&self) -> usize {
+        self.topology.number_of_rows()
+    }
+
+    pub fn number_of_columns(&self) -> usize {
+        self.topology.number_of_columns()
+    }
+
+    #[cfg(test)]
+    pub fn generate(size: usize, number_of_colours: u32) -> Self {
+        Self::generate_with_topology(TopologyKind::Square.create(size, size), number_of_colours)
+    }
+
+    #[cfg(test)]
+    pub fn generate_with_topology(topology: Rc<dyn Topology>, number_of_colours: u32) -> Self {
+        Self::generate_with(
+            topology,
+            number_of_colours,
+            &Uniform,
+            &mut StdRng::from_entropy(),
+        )
+    }
+
+    /// Generate a grid which only depends on `seed`, `size` and `number_of_colours`.
+    pub fn generate_from_seed(size: usize, number_of_colours: u32, seed: u64) -> Self {
+        Self::generate_with(
+            TopologyKind::Square.create(size, size),
+            number_of_colours,
+            &Uniform,
+            &mut StdRng::seed_from_u64(seed),
+        )
+    }
+
+    pub fn generate_with(
+        topology: Rc<dyn Topology>,
+        number_of_colours: u32,
+        generator: &dyn BoardGenerator,
+        rng: &mut StdRng,
+    ) -> Self {
+        let cells = generator.generate(&*topology, &generator::palette(number_of_colours), rng);
+
+        Self::new(topology, cells)
     }
 }
 
@@ -38,8 +236,57 @@ mod test {
 
         let grid = Grid::generate(size, 4);
 
-        assert_eq!(grid.number_of_rows, size);
-        assert_eq!(grid.number_of_columns, size);
+        assert_eq!(grid.number_of_rows(), size);
+        assert_eq!(grid.number_of_columns(), size);
         assert_eq!(grid.cells.len(), size * size);
     }
+
+    #[test]
+    pub fn generates_same_grid_from_same_seed() {
+        let grid = Grid::generate_from_seed(8, 6, 42);
+
+        assert_eq!(grid, Grid::generate_from_seed(8, 6, 42));
+        assert_ne!(grid, Grid::generate_from_seed(8, 6, 43));
+    }
+
+    #[test]
+    pub fn generates_grid_with_given_topology() {
+        let grid = Grid::generate_with_topology(TopologyKind::Hexagonal.create(3, 5), 4);
+
+        assert_eq!(grid.topology.name(), "hex");
+        assert_eq!(grid.cells.len(), 15);
+    }
+
+    #[test]
+    pub fn ensures_every_colour_covers_minimum_number_of_cells() {
+        let mut grid = Grid::new(TopologyKind::Square.create(3, 3), vec![Colour::RED; 9]);
+
+        let palette = generator::palette(4);
+        grid.ensure_colour_counts(&palette, 2, false, &mut StdRng::seed_from_u64(0));
+
+        for colour in &palette {
+            assert!(grid.cells.iter().filter(|&cell| cell == colour).count() >= 2);
+        }
+        assert_eq!(grid.cells[0], Colour::RED);
+    }
+
+    #[test]
+    pub fn keeps_mirrored_grid_symmetric_when_ensuring_colour_counts() {
+        let mut grid = Grid::new(TopologyKind::Square.create(4, 5), vec![Colour::RED; 20]);
+
+        let palette = generator::palette(4);
+        grid.ensure_colour_counts(&palette, 4, true, &mut StdRng::seed_from_u64(0));
+
+        for colour in &palette {
+            assert!(grid.cells.iter().filter(|&cell| cell == colour).count() >= 4);
+        }
+        for row in 0..4 {
+            for column in 0..5 {
+                assert_eq!(
+                    grid.cells[row * 5 + column],
+                    grid.cells[row * 5 + 4 - column]
+                );
+            }
+        }
+    }
 }
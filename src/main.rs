@@ -1,16 +1,80 @@
-use macroquad::prelude::*;
+// Without either front-end, only the protocol and tournaments are left, which leave the modes and
+// everything they keep track of unused.
+#![cfg_attr(not(any(feature = "gui", feature = "tui")), allow(dead_code))]
 
-use game::Game;
+use std::path::PathBuf;
+use std::rc::Rc;
 
+use campaign::Campaign;
+use colour::Palette;
+use config::{Config, Keybindings};
+use daily::Daily;
+use difficulty::Difficulty;
+use game::Settings;
+use generator::GeneratorKind;
+use graph::Lock;
+use mask::Mask;
+use mode::{ExactPar, Mode, TimeAttack, TimeAttackKind, Zen};
+use picture::{Dithering, Picture};
+use theme::{Appearance, Theme};
+use topology::{Connectivity, TopologyKind};
+
+#[cfg(feature = "gui")]
+mod animation;
+mod campaign;
+mod clock;
 mod colour;
+mod config;
+mod daily;
+mod difficulty;
 mod game;
+mod generator;
 mod graph;
 mod grid;
+mod mask;
+mod mode;
+mod picture;
+mod protocol;
+mod solver;
+mod statistics;
+mod storage;
+mod strategy;
+mod theme;
+mod topology;
+mod tournament;
+#[cfg(feature = "tui")]
+mod tui;
+#[cfg(feature = "gui")]
 mod ui;
 
+/// Where to play.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Frontend {
+    Window,
+    Terminal,
+
+    /// Another program plays through stdin and stdout.
+    Protocol,
+
+    /// The built-in strategies play a fixed set of boards, optionally saving every result.
+    Tournament {
+        boards_per_kind: u64,
+        results: Option<PathBuf>,
+    },
+}
+
+/// The default of an argument: the value from the configuration file if there is one, otherwise
+/// `fallback`.
+fn configured(value: Option<impl ToString>, fallback: &str) -> String {
+    value.map_or_else(|| fallback.to_string(), |value| value.to_string())
+}
+
 /// Handle command line arguments
-fn parse_args() -> (u32, u32) {
-    use clap::{value_parser, Arg, Command};
+fn parse_args() -> (Settings, Mode, Appearance, Keybindings, Frontend) {
+    use clap::builder::Resettable;
+    use clap::{value_parser, Arg, ArgAction, Command};
+
+    let config = Config::load();
 
     let matches = Command::new("Flood-It")
         .author(env!("CARGO_PKG_AUTHORS"))
@@ -20,27 +84,323 @@ fn parse_args() -> (u32, u32) {
                 .value_name("colors")
                 .help("The number of different colors")
                 .value_parser(value_parser!(u32))
-                .default_value("6"),
+                .default_value(configured(config.number_of_colours, "6")),
         )
         .arg(
             Arg::new("size")
                 .value_name("size")
                 .help("The height and width of the grid")
                 .value_parser(value_parser!(u32))
-                .default_value("14"),
+                .default_value(configured(config.size, "14")),
+        )
+        .arg(
+            Arg::new("mode")
+                .long("mode")
+                .value_name("mode")
+                .help(
+                    "How to play: within the move budget (classic), flood one board before the \
+                     time runs out (timed) or as many boards as possible (marathon), play \
+                     the board of the day (daily), a sequence of levels (campaign), one \
+                     board after another without any limits (zen) or using no more moves than \
+                     the optimal solution (exact)",
+                )
+                .value_parser(Mode::NAMES)
+                .default_value(configured(config.mode.as_ref(), "classic")),
+        )
+        .arg(
+            Arg::new("time-limit")
+                .long("time-limit")
+                .value_name("seconds")
+                .help("The time limit for timed and marathon mode [default: 120 and 300]")
+                .value_parser(value_parser!(u32).range(1..)),
+        )
+        .arg(
+            Arg::new("level")
+                .long("level")
+                .value_name("level")
+                .help("The level to play in campaign mode [default: the first one not completed]")
+                .value_parser(value_parser!(u32).range(1..)),
+        )
+        .arg(
+            Arg::new("minimum-par")
+                .long("minimum-par")
+                .value_name("moves")
+                .help("Reject boards that can be flooded in fewer moves in exact mode")
+                .value_parser(value_parser!(u32))
+                .default_value("0"),
+        )
+        .arg(
+            Arg::new("topology")
+                .long("topology")
+                .value_name("topology")
+                .help("The shape of the cells")
+                .value_parser(TopologyKind::NAMES)
+                .default_value(configured(config.topology.as_ref(), "square")),
+        )
+        .arg(
+            Arg::new("connectivity")
+                .long("connectivity")
+                .value_name("neighbours")
+                .help(
+                    "Whether cells of the same colour touching only at a corner are \
+                     connected (8) or not (4)",
+                )
+                .value_parser(["4", "8"])
+                .default_value("4"),
+        )
+        .arg(
+            Arg::new("mask")
+                .long("mask")
+                .value_name("file")
+                .help(
+                    "A text file giving the shape of the board, with '.' for cells and '#' for \
+                     holes; replaces the size",
+                )
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("difficulty")
+                .long("difficulty")
+                .value_name("difficulty")
+                .help(
+                    "Generate boards taking a small (easy), moderate (medium) or large (hard) \
+                     part of the move budget [default: random boards]",
+                )
+                .value_parser(Difficulty::NAMES),
+        )
+        .arg(
+            Arg::new("generator")
+                .long("generator")
+                .value_name("generator")
+                .help(
+                    "How to color new boards: every cell at random (uniform), in blobs \
+                     (clusters), mirrored left to right (symmetric), in rows (stripes) or \
+                     mostly in the first color (biased)",
+                )
+                .value_parser(GeneratorKind::NAMES)
+                .default_value(configured(config.generator.as_ref(), "uniform")),
+        )
+        .arg(
+            Arg::new("seed")
+                .long("seed")
+                .value_name("seed")
+                .help("Generate the same board every time")
+                .value_parser(value_parser!(u64)),
+        )
+        .arg(
+            Arg::new("minimum-cells")
+                .long("minimum-cells")
+                .value_name("cells")
+                .help("The smallest number of cells of each color on a new board")
+                .value_parser(value_parser!(usize))
+                .default_value("1"),
+        )
+        .arg(
+            Arg::new("palette")
+                .long("palette")
+                .value_name("file")
+                .help(
+                    "A built-in palette (default, deuteranopia, protanopia or tritanopia) or a \
+                     text file listing the colors to use as #rrggbb, separated by white space",
+                )
+                .value_parser(value_parser!(PathBuf))
+                .default_value(match &config.palette {
+                    Some(palette) => Resettable::Value(palette.clone().into()),
+                    None => Resettable::Reset,
+                }),
+        )
+        .arg(
+            Arg::new("theme")
+                .long("theme")
+                .value_name("theme")
+                .help(
+                    "The look of the board: classic, outlined, tiles, bubbles or one defined in \
+                     themes.toml in the configuration directory",
+                )
+                .default_value(configured(config.theme.as_ref(), "classic")),
+        )
+        .arg(
+            Arg::new("animation-speed")
+                .long("animation-speed")
+                .value_name("cells")
+                .help("How many cells further a move spreads per second; 0 shows moves at once")
+                .value_parser(value_parser!(f64))
+                .default_value(configured(config.animation_speed, "30")),
+        )
+        .arg(
+            Arg::new("tui")
+                .long("tui")
+                .help("Play in the terminal instead of a window")
+                .action(ArgAction::SetTrue)
+                .overrides_with("no-tui"),
+        )
+        .arg(
+            Arg::new("no-tui")
+                .long("no-tui")
+                .help("Play in a window even if config.toml says otherwise")
+                .action(ArgAction::SetTrue)
+                .overrides_with("tui"),
+        )
+        .arg(
+            Arg::new("protocol")
+                .long("protocol")
+                .help(
+                    "Play through a line-based protocol on stdin and stdout, e.g. to let a bot \
+                     play",
+                )
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("tournament")
+                .long("tournament")
+                .help("Let the built-in strategies play the same boards and compare them")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("boards")
+                .long("boards")
+                .value_name("boards")
+                .help("The number of tournament boards of every size and number of colors")
+                .value_parser(value_parser!(u64).range(1..))
+                .default_value("5"),
+        )
+        .arg(
+            Arg::new("results")
+                .long("results")
+                .value_name("file")
+                .help(
+                    "Save the result of every tournament game, as JSON if the file ends in .json \
+                     and as CSV otherwise",
+                )
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("symbols")
+                .long("symbols")
+                .help("Mark every color with its own shape")
+                .action(ArgAction::SetTrue)
+                .overrides_with("no-symbols"),
+        )
+        .arg(
+            Arg::new("no-symbols")
+                .long("no-symbols")
+                .help("Do not mark the colors with shapes even if config.toml says otherwise")
+                .action(ArgAction::SetTrue)
+                .overrides_with("symbols"),
+        )
+        .arg(
+            Arg::new("image")
+                .long("image")
+                .value_name("file")
+                .help("Turn a PNG image into the board instead of generating random boards")
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("dithering")
+                .long("dithering")
+                .value_name("dithering")
+                .help("How to approximate the colors of the image")
+                .value_parser(Dithering::NAMES)
+                .default_value("none"),
+        )
+        .arg(
+            Arg::new("locked")
+                .long("locked")
+                .value_name("cells")
+                .help(
+                    "The number of locked cells, which only join the flood once it has reached \
+                     them with their color twice",
+                )
+                .value_parser(value_parser!(usize))
+                .default_value("0"),
+        )
+        .arg(
+            Arg::new("unlock-after")
+                .long("unlock-after")
+                .value_name("moves")
+                .help("Unlock locked cells after the given number of moves instead")
+                .value_parser(value_parser!(u32).range(1..)),
+        )
+        .arg(
+            Arg::new("print-config")
+                .long("print-config")
+                .help(
+                    "Print the configuration resulting from config.toml in the configuration \
+                     directory and the other arguments, in the format of config.toml",
+                )
+                .action(ArgAction::SetTrue),
         )
         .get_matches();
 
+    // A flag on the command line beats its opposite in the configuration file.
+    let flag = |id: &str, configured: Option<bool>| {
+        if matches.get_flag(id) {
+            true
+        } else if matches.get_flag(&format!("no-{}", id)) {
+            false
+        } else {
+            configured.unwrap_or(false)
+        }
+    };
+    let tui = flag("tui", config.tui);
+    let show_symbols = flag("symbols", config.symbols);
+
+    if matches.get_flag("print-config") {
+        let string = |id: &str| matches.get_one::<String>(id).cloned();
+        let effective = Config {
+            number_of_colours: matches.get_one::<u32>("colors").copied(),
+            size: matches.get_one::<u32>("size").copied(),
+            mode: string("mode"),
+            topology: string("topology"),
+            generator: string("generator"),
+            theme: string("theme"),
+            palette: matches
+                .get_one::<PathBuf>("palette")
+                .map(|path| path.display().to_string()),
+            animation_speed: matches.get_one::<f64>("animation-speed").copied(),
+            symbols: Some(show_symbols),
+            tui: Some(tui),
+            keys: config.keys,
+        };
+        print!("{}", effective.to_toml());
+        std::process::exit(0);
+    }
+
+    let mut themes = Theme::built_in();
+    if let Some(path) = storage::config_file("themes.toml").filter(|path| path.exists()) {
+        match Theme::load(&path) {
+            Ok(loaded) => themes.extend(loaded),
+            Err(error) => eprintln!("Ignoring themes: {}", error),
+        }
+    }
+    let theme = {
+        let name = matches.get_one::<String>("theme").expect("Invalid theme");
+        themes
+            .iter()
+            .rposition(|theme| theme.name == *name)
+            .unwrap_or_else(|| panic!("There is no theme called {}.", name))
+    };
+
+    // The palette of the theme applies unless another one is given explicitly.
+    let palette = match matches.get_one::<PathBuf>("palette") {
+        Some(path) => path
+            .to_str()
+            .and_then(Palette::from_name)
+            .unwrap_or_else(|| Palette::load(path).unwrap_or_else(|error| panic!("{}", error))),
+        None => themes[theme]
+            .palette
+            .as_deref()
+            .cloned()
+            .unwrap_or_default(),
+    };
+
     let colors = {
         let tmp = *matches
             .get_one::<u32>("colors")
             .expect("Invalid number of colors") as usize;
-        let maximum_number_of_colours = colour::ALL_COLOURS.len();
-        if tmp < 3 || tmp > maximum_number_of_colours {
-            panic!(
-                "Flood-It only supports 3 through {} (inclusive) colors.",
-                maximum_number_of_colours,
-            );
+        // How many colours there can be at most depends on the palette, see below.
+        if tmp < 3 {
+            panic!("Flood-It needs at least 3 colors.");
         } else {
             tmp as u32
         }
@@ -55,33 +415,250 @@ fn parse_args() -> (u32, u32) {
         }
     };
 
-    (colors, size)
+    let topology = matches
+        .get_one::<String>("topology")
+        .and_then(|name| TopologyKind::from_name(name))
+        .expect("Invalid topology");
+
+    let connectivity = match matches
+        .get_one::<String>("connectivity")
+        .map(String::as_str)
+    {
+        Some("8") => {
+            if topology == TopologyKind::Hexagonal {
+                panic!("Hexagonal cells only ever touch along an edge.");
+            }
+            Connectivity::EdgesAndCorners
+        }
+        _ => Connectivity::Edges,
+    };
+
+    let mask = matches.get_one::<PathBuf>("mask").map(|path| {
+        let mask = Mask::load(path).unwrap_or_else(|error| panic!("{}", error));
+        let topology = topology.create(mask.number_of_rows(), mask.number_of_columns());
+        if !mask.is_connected(topology, connectivity) {
+            panic!("The cells of the mask have to be connected to each other.");
+        }
+        Rc::new(mask)
+    });
+
+    let locked_cells = *matches
+        .get_one::<usize>("locked")
+        .expect("Invalid number of cells");
+    let lock = match matches.get_one::<u32>("unlock-after") {
+        Some(&moves) => Lock::Moves(moves),
+        None => Lock::Matches(2),
+    };
+
+    let difficulty = matches
+        .get_one::<String>("difficulty")
+        .map(|name| Difficulty::from_name(name).expect("Invalid difficulty"));
+    // The difficulty is estimated by the solver, which does not know about locks.
+    if difficulty.is_some() && locked_cells > 0 {
+        panic!("The difficulty of boards with locked cells cannot be estimated.");
+    }
+
+    let generator = matches
+        .get_one::<String>("generator")
+        .and_then(|name| GeneratorKind::from_name(name))
+        .expect("Invalid generator");
+    // Boards are mirrored column by column, which only matches the geometry of square cells.
+    if generator == GeneratorKind::Symmetric
+        && matches!(topology, TopologyKind::Hexagonal | TopologyKind::Triangular)
+    {
+        panic!("Only square and torus boards can be symmetric.");
+    }
+
+    let picture = matches
+        .get_one::<PathBuf>("image")
+        .map(|path| Rc::new(Picture::load(path).unwrap_or_else(|error| panic!("{}", error))));
+    let dithering = matches
+        .get_one::<String>("dithering")
+        .and_then(|name| Dithering::from_name(name))
+        .expect("Invalid dithering");
+
+    let settings = Settings {
+        size,
+        number_of_colours: colors,
+        topology,
+        connectivity,
+        mask,
+        locked_cells,
+        lock,
+        difficulty,
+        generator,
+        seed: matches.get_one::<u64>("seed").copied(),
+        minimum_cells_per_colour: *matches
+            .get_one::<usize>("minimum-cells")
+            .expect("Invalid number of cells"),
+        picture,
+        dithering,
+        palette: Rc::new(palette),
+    };
+
+    let time_limit = matches.get_one::<u32>("time-limit").copied();
+    let mode = match matches.get_one::<String>("mode").map(String::as_str) {
+        Some("timed") => Mode::TimeAttack(TimeAttack::new(
+            TimeAttackKind::SingleBoard,
+            time_limit.unwrap_or(120) as f64,
+        )),
+        Some("marathon") => Mode::TimeAttack(TimeAttack::new(
+            TimeAttackKind::Marathon,
+            time_limit.unwrap_or(300) as f64,
+        )),
+        Some("daily") => Mode::Daily(Daily::today()),
+        Some("campaign") => {
+            let campaign = match matches.get_one::<u32>("level") {
+                Some(&level) => Campaign::start_at(level as usize - 1)
+                    .unwrap_or_else(|error| panic!("{}", error)),
+                None => Campaign::resume(),
+            };
+            Mode::Campaign(campaign)
+        }
+        Some("zen") => Mode::Zen(Zen::default()),
+        Some("exact") if locked_cells > 0 => {
+            panic!("The optimal number of moves is unknown for boards with locked cells.")
+        }
+        Some("exact") => Mode::ExactPar(ExactPar::new(
+            *matches
+                .get_one::<u32>("minimum-par")
+                .expect("Invalid minimum par"),
+        )),
+        _ => Mode::Classic,
+    };
+
+    let number_of_colours = mode.maximum_number_of_colours(&settings);
+    if settings.palette.len() < number_of_colours as usize {
+        panic!(
+            "The palette has only {} colors, but the boards of this mode need up to {}.",
+            settings.palette.len(),
+            number_of_colours,
+        );
+    }
+
+    let appearance = Appearance {
+        #[cfg(feature = "gui")]
+        themes,
+        #[cfg(feature = "gui")]
+        theme,
+        show_symbols,
+        #[cfg(feature = "gui")]
+        animation_speed: matches
+            .get_one::<f64>("animation-speed")
+            .copied()
+            .filter(|speed| *speed >= 0.0)
+            .expect("Invalid animation speed"),
+    };
+
+    let frontend = match (tui, cfg!(feature = "gui")) {
+        _ if matches.get_flag("tournament") => Frontend::Tournament {
+            boards_per_kind: *matches
+                .get_one::<u64>("boards")
+                .expect("Invalid number of boards"),
+            results: matches.get_one::<PathBuf>("results").cloned(),
+        },
+        // The protocol has no clock, and it ends every game at the move budget.
+        _ if matches.get_flag("protocol") && !mode.has_budget() => panic!(
+            "The protocol only supports modes with a move budget, not {}.",
+            mode.name()
+        ),
+        _ if matches.get_flag("protocol") => Frontend::Protocol,
+        (false, true) => Frontend::Window,
+        _ if cfg!(feature = "tui") => Frontend::Terminal,
+        _ => panic!(
+            "Flood-It was built without support for playing in the terminal; try --protocol."
+        ),
+    };
+
+    (settings, mode, appearance, config.keys, frontend)
+}
+
+// Without either front-end, only the protocol is left, which does not draw anything.
+#[cfg_attr(not(any(feature = "gui", feature = "tui")), allow(unused_variables))]
+fn main() {
+    let (settings, mode, appearance, keys, frontend) = parse_args();
+
+    match frontend {
+        #[cfg(feature = "gui")]
+        Frontend::Window => {
+            macroquad::Window::new("Flood-It", play_in_window(settings, mode, appearance, keys))
+        }
+        #[cfg(feature = "tui")]
+        Frontend::Terminal => {
+            let mut game = mode.create_game(&settings);
+            tui::Tui::create(settings, mode, appearance.show_symbols, keys, &game)
+                .run(&mut game)
+                .unwrap_or_else(|error| panic!("Cannot use the terminal: {}", error));
+        }
+        Frontend::Protocol => {
+            let mut game = mode.create_game(&settings);
+            protocol::run(&mut game, std::io::stdin().lock(), std::io::stdout().lock())
+                .unwrap_or_else(|error| panic!("Cannot play through the protocol: {}", error));
+        }
+        Frontend::Tournament {
+            boards_per_kind,
+            results,
+        } => {
+            let records =
+                tournament::run(&mut strategy::all(), &tournament::boards(boards_per_kind));
+            print!(
+                "{}",
+                tournament::format_leaderboard(&tournament::leaderboard(&records))
+            );
+            if let Some(path) = results {
+                tournament::save(&records, &path).unwrap_or_else(|error| panic!("{}", error));
+            }
+        }
+        #[allow(unreachable_patterns)]
+        _ => unreachable!(),
+    }
 }
 
-#[macroquad::main("Flood-It")]
-async fn main() {
-    let (number_of_colours, size) = parse_args();
+#[cfg(feature = "gui")]
+async fn play_in_window(settings: Settings, mode: Mode, appearance: Appearance, keys: Keybindings) {
+    use macroquad::prelude::*;
+    use ui::is_pressed;
 
-    let mut game = Game::create(size, number_of_colours);
-    let mut ui = crate::ui::Ui::create(size, number_of_colours);
+    let mut game = mode.create_game(&settings);
+    let mut ui = ui::Ui::create(settings, mode, appearance);
 
     loop {
-        if let Some(KeyCode::Q) = get_last_key_pressed() {
+        if is_pressed(&keys.quit) {
+            ui.handle_quit(&game);
             break;
         }
 
         if is_mouse_button_pressed(MouseButton::Left) {
             ui.handle_click(&mut game, mouse_position());
         }
+        ui.handle_hover(&game, mouse_position());
 
-        if is_key_pressed(KeyCode::Space)
-            || is_key_pressed(KeyCode::N)
-            || is_key_pressed(KeyCode::R)
-        {
+        if is_pressed(&keys.new_board) {
             ui.handle_key_press(&mut game);
         }
 
-        ui.resize();
+        if is_pressed(&keys.pause) {
+            ui.handle_pause(&game);
+        }
+
+        if is_pressed(&keys.palette) {
+            ui.handle_palette_switch();
+        }
+
+        if is_pressed(&keys.theme) {
+            ui.handle_theme_switch();
+        }
+
+        if is_pressed(&keys.symbols) {
+            ui.handle_symbol_toggle();
+        }
+
+        if is_pressed(&keys.statistics) {
+            ui.handle_statistics_toggle();
+        }
+
+        ui.update(&mut game, get_time());
+        ui.resize(&game);
         ui.render(&game);
 
         next_frame().await
@@ -0,0 +1,152 @@
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::colour::Colour;
+use crate::graph::Graph;
+use crate::grid::Grid;
+use crate::topology::{Connectivity, Topology};
+
+/// The shape of a board, i.e. which of the cells of the surrounding rectangle are void.  Void
+/// cells never change colour and do not connect the cells around them.
+///
+/// In a mask file, every line is a row of the board.  A `.` marks a cell, a `#` or a space marks
+/// a void cell.  Rows shorter than the longest one are filled up with void cells.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Mask {
+    rows: usize,
+    columns: usize,
+    void: Vec<bool>,
+}
+
+impl Mask {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|error| format!("Cannot read mask {}: {}", path.display(), error))?;
+
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+        while lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+
+        let rows = lines.len();
+        let columns = lines
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut void = vec![true; rows * columns];
+
+        for (row, line) in lines.iter().enumerate() {
+            for (column, character) in line.chars().enumerate() {
+                void[row * columns + column] = match character {
+                    '.' => false,
+                    '#' | ' ' => true,
+                    _ => {
+                        return Err(format!(
+                            "Unexpected character '{}' in line {} of the mask.",
+                            character,
+                            row + 1
+                        ))
+                    }
+                };
+            }
+        }
+
+        if void.iter().all(|&void| void) {
+            return Err("The mask does not contain any cells.".to_string());
+        }
+
+        Ok(Self {
+            rows,
+            columns,
+            void,
+        })
+    }
+
+    pub fn number_of_rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn number_of_columns(&self) -> usize {
+        self.columns
+    }
+
+    /// The number of cells which are not void.
+    pub fn number_of_cells(&self) -> usize {
+        self.void.iter().filter(|&&void| !void).count()
+    }
+
+    pub fn is_void(&self, cell: usize) -> bool {
+        self.void[cell]
+    }
+
+    /// Whether all cells can be reached from each other, i.e. whether a board of this shape can be
+    /// flooded completely.
+    pub fn is_connected(&self, topology: Rc<dyn Topology>, connectivity: Connectivity) -> bool {
+        let cells = vec![Colour(0); topology.number_of_cells()];
+        let grid = Grid::new(topology, cells)
+            .with_connectivity(connectivity)
+            .with_mask(Some(Rc::new(self.clone())));
+
+        Graph::create(&grid).components.len() == 1
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::topology::TopologyKind;
+
+    #[test]
+    fn should_fill_up_short_rows_with_void_cells() {
+        let mask = Mask::parse("#..#\n...\n\n").unwrap();
+
+        assert_eq!(mask.number_of_rows(), 2);
+        assert_eq!(mask.number_of_columns(), 4);
+        assert_eq!(mask.number_of_cells(), 5);
+        assert!(mask.is_void(0));
+        assert!(!mask.is_void(6));
+        assert!(mask.is_void(7));
+    }
+
+    #[test]
+    fn should_connect_cells_of_bundled_masks() {
+        let masks = [
+            include_str!("../masks/circle.txt"),
+            include_str!("../masks/heart.txt"),
+            include_str!("../masks/maze.txt"),
+        ];
+
+        for text in masks {
+            let mask = Mask::parse(text).unwrap();
+            let topology =
+                TopologyKind::Square.create(mask.number_of_rows(), mask.number_of_columns());
+
+            assert!(mask.is_connected(topology, Connectivity::Edges));
+        }
+    }
+
+    #[test]
+    fn should_reject_unknown_characters() {
+        assert!(Mask::parse("..\n.x").is_err());
+    }
+
+    #[test]
+    fn should_reject_mask_without_cells() {
+        assert!(Mask::parse("##\n##").is_err());
+    }
+
+    #[test]
+    fn should_not_connect_cells_through_void_cells() {
+        let mask = Mask::parse(".#\n#.").unwrap();
+        let topology = || TopologyKind::Square.create(2, 2);
+
+        assert!(!mask.is_connected(topology(), Connectivity::Edges));
+        assert!(mask.is_connected(topology(), Connectivity::EdgesAndCorners));
+    }
+}
@@ -0,0 +1,694 @@
+use serde::{Deserialize, Serialize};
+
+use crate::campaign::{Campaign, LEVELS};
+use crate::clock::{format_time, Clock};
+use crate::daily::{self, Daily};
+use crate::game::{Game, GameState, Settings, MAXIMUM_ATTEMPTS};
+use crate::solver;
+use crate::statistics::Statistics;
+use crate::storage;
+
+const TIME_ATTACK_FILE: &str = "time_attack.toml";
+
+/// The different ways to play Flood-It.
+pub enum Mode {
+    /// Flood a single board without exceeding the move budget.
+    Classic,
+
+    /// Race against the clock instead of the move budget.
+    TimeAttack(TimeAttack),
+
+    /// Play the board of the day and try to beat the solver.
+    Daily(Daily),
+
+    /// Work through a fixed sequence of increasingly difficult levels.
+    Campaign(Campaign),
+
+    /// Flood one board after another without any limits.
+    Zen(Zen),
+
+    /// Flood a board using no more moves than the solver.
+    ExactPar(ExactPar),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeAttackKind {
+    /// Flood a single board before the time runs out.
+    SingleBoard,
+
+    /// Flood as many boards as possible before the time runs out.
+    Marathon,
+}
+
+impl TimeAttackKind {
+    /// The name of the mode on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            TimeAttackKind::SingleBoard => "timed",
+            TimeAttackKind::Marathon => "marathon",
+        }
+    }
+}
+
+/// The outcome of one round of time attack.  These are kept apart from the move counts of
+/// classic games as the two are not comparable.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TimeAttackResult {
+    pub kind: TimeAttackKind,
+    /// Time limit in seconds, as results are only comparable for the same limit.
+    pub limit: f64,
+    pub boards_cleared: u32,
+    /// Playing time in seconds.
+    pub time: f64,
+    /// Moves used across all boards of the round.
+    pub moves: u32,
+}
+
+impl TimeAttackResult {
+    /// Whether the result beats `other`: flooding a single board faster, or more boards in a
+    /// marathon, using fewer moves in case of a tie.
+    fn beats(&self, other: &TimeAttackResult) -> bool {
+        match self.kind {
+            TimeAttackKind::SingleBoard => self.boards_cleared > 0 && self.time < other.time,
+            TimeAttackKind::Marathon => {
+                (self.boards_cleared, other.moves) > (other.boards_cleared, self.moves)
+            }
+        }
+    }
+}
+
+/// The results of all rounds of time attack ever played.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TimeAttackResults {
+    #[serde(default)]
+    result: Vec<TimeAttackResult>,
+}
+
+impl TimeAttackResults {
+    /// The best result of the given kind and time limit, if any round counts at all.
+    fn best(&self, kind: TimeAttackKind, limit: f64) -> Option<&TimeAttackResult> {
+        self.result
+            .iter()
+            .filter(|result| result.kind == kind && result.limit == limit)
+            .filter(|result| result.boards_cleared > 0)
+            .fold(None, |best, result| match best {
+                Some(best) if !result.beats(best) => Some(best),
+                _ => Some(result),
+            })
+    }
+}
+
+pub struct TimeAttack {
+    pub kind: TimeAttackKind,
+
+    /// Time limit in seconds.
+    limit: f64,
+
+    pub clock: Clock,
+
+    /// Boards cleared in the current round.
+    pub boards_cleared: u32,
+
+    /// Moves used on the boards of the current round which have already been cleared.
+    moves: u32,
+}
+
+impl TimeAttack {
+    pub fn new(kind: TimeAttackKind, limit: f64) -> Self {
+        Self {
+            kind,
+            limit,
+            clock: Clock::new(limit),
+            boards_cleared: 0,
+            moves: 0,
+        }
+    }
+
+    /// Start a new round, i.e. reset the clock and the counters.
+    fn restart(&mut self) {
+        self.clock = Clock::new(self.limit);
+        self.boards_cleared = 0;
+        self.moves = 0;
+    }
+
+    /// Store the result of the round and tell the player about it through `game.verdict`.
+    fn finish_round(&mut self, game: &mut Game) {
+        let result = TimeAttackResult {
+            kind: self.kind,
+            limit: self.limit,
+            boards_cleared: self.boards_cleared,
+            time: self.clock.elapsed(),
+            moves: self.moves + game.number_of_clicks,
+        };
+
+        let mut verdict = match (result.kind, game.state) {
+            (TimeAttackKind::SingleBoard, GameState::Solved) => format!(
+                "You win! You flooded the board in {} using {} moves.",
+                format_time(result.time),
+                result.moves,
+            ),
+            (TimeAttackKind::SingleBoard, _) => "Time is up. You lose.".to_string(),
+            (TimeAttackKind::Marathon, _) => format!(
+                "Time is up. You flooded {} boards using {} moves.",
+                result.boards_cleared, result.moves,
+            ),
+        };
+
+        // Results are kept apart from the statistics of other modes, which count moves only.
+        let mut results: TimeAttackResults = storage::load(TIME_ATTACK_FILE);
+        let previous_best = results.best(result.kind, result.limit).cloned();
+        if previous_best
+            .as_ref()
+            .is_some_and(|best| result.beats(best))
+        {
+            verdict.push_str(" That is a new record!");
+        }
+        results.result.push(result);
+        storage::save(TIME_ATTACK_FILE, &results);
+
+        game.verdict = Some(verdict);
+    }
+}
+
+/// A running tally of the boards flooded in zen mode.
+#[derive(Clone, Debug, Default)]
+pub struct Zen {
+    pub boards_cleared: u32,
+    total_moves: u32,
+}
+
+impl Zen {
+    pub fn record(&mut self, moves: u32) {
+        self.boards_cleared += 1;
+        self.total_moves += moves;
+    }
+
+    /// The average number of moves per board, if any board has been cleared yet.
+    pub fn average_moves(&self) -> Option<f64> {
+        if self.boards_cleared == 0 {
+            None
+        } else {
+            Some(self.total_moves as f64 / self.boards_cleared as f64)
+        }
+    }
+}
+
+/// Boards whose move budget is the smallest number of moves that floods them.
+#[derive(Clone, Debug)]
+pub struct ExactPar {
+    /// Reject boards which can be flooded in fewer moves.
+    pub minimum_par: u32,
+}
+
+impl ExactPar {
+    pub fn new(minimum_par: u32) -> Self {
+        Self { minimum_par }
+    }
+
+    /// Generate boards until one is found whose optimum is known and at least `minimum_par`.
+    /// Should that take too long, the hardest board found is used instead.
+    pub fn create_game(&self, settings: &Settings) -> Game {
+        // One generator for all attempts, so that a seed does not yield the same board each time.
+        let mut rng = settings.rng();
+        let mut hardest: Option<Game> = None;
+        let mut hardest_unsolved: Option<Game> = None;
+
+        for _ in 0..MAXIMUM_ATTEMPTS {
+            let mut game = Game::create_with(settings, &mut rng);
+            let solution = solver::solve(&game.graph, &game.start, solver::DEFAULT_SEARCH_LIMIT);
+            game.allowed_clicks = solution.moves.len() as u32;
+
+            let best = if solution.optimal {
+                game.optimum = Some(game.allowed_clicks);
+                if game.allowed_clicks >= self.minimum_par {
+                    return game;
+                }
+                &mut hardest
+            } else {
+                &mut hardest_unsolved
+            };
+            if best
+                .as_ref()
+                .is_none_or(|best| game.allowed_clicks > best.allowed_clicks)
+            {
+                *best = Some(game);
+            }
+        }
+
+        // Should the solver have given up on every board, settle for the best solution it found.
+        // Without an optimum, the game tells the player the budget is only an upper bound.
+        hardest
+            .or(hardest_unsolved)
+            .expect("There is at least one attempt")
+    }
+}
+
+/// What the UI has to do after a board has been flooded completely.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Continuation {
+    /// Keep showing the flooded board until the player asks for a new one.
+    Wait,
+
+    /// Replace the board by a new one right away.
+    NextBoard,
+}
+
+impl Mode {
+    pub const NAMES: [&'static str; 7] = [
+        "classic", "timed", "marathon", "daily", "campaign", "zen", "exact",
+    ];
+
+    /// The name of the mode on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mode::Classic => "classic",
+            Mode::TimeAttack(time_attack) => time_attack.kind.name(),
+            Mode::Daily(_) => "daily",
+            Mode::Campaign(_) => "campaign",
+            Mode::Zen(_) => "zen",
+            Mode::ExactPar(_) => "exact",
+        }
+    }
+
+    /// Generate the next board to play.  Modes with boards of their own ignore `settings`.
+    pub fn create_game(&self, settings: &Settings) -> Game {
+        match self {
+            Mode::Classic | Mode::TimeAttack(_) | Mode::Zen(_) => Game::create(settings),
+            Mode::Daily(daily) => daily.create_game(),
+            Mode::Campaign(campaign) => campaign.create_game(),
+            Mode::ExactPar(exact_par) => exact_par.create_game(settings),
+        }
+    }
+
+    /// The largest number of colours the boards of the mode can have, which the palette has to
+    /// provide.
+    pub fn maximum_number_of_colours(&self, settings: &Settings) -> u32 {
+        match self {
+            Mode::Classic | Mode::TimeAttack(_) | Mode::Zen(_) | Mode::ExactPar(_) => {
+                settings.number_of_colours
+            }
+            Mode::Daily(_) => daily::NUMBERS_OF_COLOURS.into_iter().max().unwrap_or(0),
+            Mode::Campaign(_) => LEVELS
+                .iter()
+                .map(|level| level.number_of_colours)
+                .max()
+                .unwrap_or(0),
+        }
+    }
+
+    /// Prepare the mode for a freshly generated game.
+    pub fn start(&mut self) {
+        match self {
+            Mode::Classic
+            | Mode::Daily(_)
+            | Mode::Campaign(_)
+            | Mode::Zen(_)
+            | Mode::ExactPar(_) => (),
+            Mode::TimeAttack(time_attack) => time_attack.restart(),
+        }
+    }
+
+    /// Advance the clock, if there is one, and end the game when time is up.
+    pub fn update(&mut self, game: &mut Game, now: f64) {
+        match self {
+            Mode::Classic
+            | Mode::Daily(_)
+            | Mode::Campaign(_)
+            | Mode::Zen(_)
+            | Mode::ExactPar(_) => (),
+            Mode::TimeAttack(time_attack) => {
+                if !matches!(game.state, GameState::Solving) {
+                    return;
+                }
+
+                time_attack.clock.tick(now);
+                if time_attack.clock.is_expired() {
+                    game.state = GameState::TimeUp;
+                    time_attack.finish_round(game);
+
+                    // The boards of a marathon count one by one as they are flooded.
+                    if time_attack.kind == TimeAttackKind::SingleBoard {
+                        Statistics::record(time_attack.kind.name(), game, Some(false), None);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn toggle_pause(&mut self) {
+        match self {
+            Mode::Classic
+            | Mode::Daily(_)
+            | Mode::Campaign(_)
+            | Mode::Zen(_)
+            | Mode::ExactPar(_) => (),
+            Mode::TimeAttack(time_attack) => time_attack.clock.toggle_pause(),
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        match self {
+            Mode::Classic
+            | Mode::Daily(_)
+            | Mode::Campaign(_)
+            | Mode::Zen(_)
+            | Mode::ExactPar(_) => false,
+            Mode::TimeAttack(time_attack) => time_attack.clock.is_paused(),
+        }
+    }
+
+    /// Whether to hide the board, which happens while the clock is paused so the break cannot be
+    /// used to plan ahead.
+    pub fn hides_board(&self) -> bool {
+        self.is_paused()
+    }
+
+    /// Whether the number of moves is limited, as opposed to the time or not at all.
+    pub fn has_budget(&self) -> bool {
+        match self {
+            Mode::Classic | Mode::Daily(_) | Mode::Campaign(_) | Mode::ExactPar(_) => true,
+            Mode::TimeAttack(_) | Mode::Zen(_) => false,
+        }
+    }
+
+    fn record_statistics(&self, game: &Game, won: Option<bool>) {
+        let budget = self.has_budget().then_some(game.allowed_clicks);
+        Statistics::record(self.name(), game, won, budget);
+    }
+
+    /// Count the board of `game` as lost if the player gives up on it, e.g. by quitting, so that
+    /// skipping boards does not keep a winning streak alive.  Boards nothing has been done on
+    /// yet do not count.
+    pub fn abandon(&self, game: &Game) {
+        let can_be_lost = match self {
+            Mode::TimeAttack(time_attack) => time_attack.kind == TimeAttackKind::SingleBoard,
+            Mode::Zen(_) => false,
+            _ => true,
+        };
+
+        if can_be_lost && game.state == GameState::Solving && game.number_of_clicks > 0 {
+            self.record_statistics(game, Some(false));
+        }
+    }
+
+    /// Record that the board of `game` has been flooded completely.  Unless the next board follows
+    /// right away, `game.verdict` tells the player how they did.
+    pub fn board_solved(&mut self, game: &mut Game) -> Continuation {
+        // Beating the clock is what counts against it; everywhere else it is the move budget.
+        // Marathon and zen boards are neither won nor lost, they just keep coming.
+        let won = match self {
+            Mode::TimeAttack(time_attack) => {
+                (time_attack.kind == TimeAttackKind::SingleBoard).then_some(true)
+            }
+            Mode::Zen(_) => None,
+            _ => Some(game.number_of_clicks <= game.allowed_clicks),
+        };
+        self.record_statistics(game, won);
+
+        match self {
+            Mode::Classic => {
+                game.state = GameState::Solved;
+                game.verdict = Some(if game.number_of_clicks <= game.allowed_clicks {
+                    format!(
+                        "You win! You used {} out of {} available moves.",
+                        game.number_of_clicks, game.allowed_clicks,
+                    )
+                } else {
+                    format!(
+                        "You lose. You took {} moves but should have finished in {}.",
+                        game.number_of_clicks, game.allowed_clicks,
+                    )
+                });
+
+                Continuation::Wait
+            }
+
+            Mode::TimeAttack(time_attack) => {
+                time_attack.boards_cleared += 1;
+
+                match time_attack.kind {
+                    TimeAttackKind::SingleBoard => {
+                        game.state = GameState::Solved;
+                        time_attack.finish_round(game);
+                        Continuation::Wait
+                    }
+                    TimeAttackKind::Marathon => {
+                        time_attack.moves += game.number_of_clicks;
+                        Continuation::NextBoard
+                    }
+                }
+            }
+
+            Mode::Daily(daily) => {
+                game.state = GameState::Solved;
+
+                let mut verdict = format!(
+                    "You flooded the board of {} in {} moves (par {}).",
+                    daily.date, game.number_of_clicks, daily.par,
+                );
+                if daily.record(game.number_of_clicks) {
+                    verdict.push_str(" That is your best result today!");
+                }
+                game.verdict = Some(verdict);
+
+                Continuation::Wait
+            }
+
+            Mode::Campaign(campaign) => {
+                game.state = GameState::Solved;
+
+                let verdict = if game.number_of_clicks > game.allowed_clicks {
+                    format!(
+                        "You lose. You took {} moves but should have finished in {}. Try again!",
+                        game.number_of_clicks, game.allowed_clicks,
+                    )
+                } else if campaign.is_last_level() {
+                    "You win! You completed the last level.".to_string()
+                } else {
+                    format!(
+                        "You win! You completed level {} and unlocked level {}.",
+                        campaign.level + 1,
+                        campaign.level + 2,
+                    )
+                };
+                if game.number_of_clicks <= game.allowed_clicks {
+                    campaign.complete_level();
+                }
+                game.verdict = Some(verdict);
+
+                Continuation::Wait
+            }
+
+            Mode::Zen(zen) => {
+                zen.record(game.number_of_clicks);
+                Continuation::NextBoard
+            }
+
+            Mode::ExactPar(_) => {
+                game.state = GameState::Solved;
+
+                let won = game.number_of_clicks <= game.allowed_clicks;
+                game.verdict = Some(match (won, game.optimum) {
+                    (true, Some(_)) => format!(
+                        "Optimal! You flooded the board in the minimum of {} moves.",
+                        game.number_of_clicks,
+                    ),
+                    (true, None) => format!(
+                        "You win! You matched the best solution found, {} moves, though there \
+                         may be a shorter one.",
+                        game.allowed_clicks,
+                    ),
+                    (false, None) => format!(
+                        "You lose. You took {} moves but the board can be flooded in at most {}.",
+                        game.number_of_clicks, game.allowed_clicks,
+                    ),
+                    (false, Some(_)) => format!(
+                        "You lose. You took {} moves but the board can be flooded in {}.",
+                        game.number_of_clicks, game.allowed_clicks,
+                    ),
+                });
+
+                Continuation::Wait
+            }
+        }
+    }
+
+    /// A line of text summarising the progress of the current game.
+    pub fn status(&self, game: &Game) -> String {
+        match self {
+            Mode::Classic => format!("Moves: {}/{}", game.number_of_clicks, game.allowed_clicks),
+
+            Mode::TimeAttack(time_attack) => {
+                let time = format_time(time_attack.clock.remaining());
+                let paused = if time_attack.clock.is_paused() {
+                    " (paused)"
+                } else {
+                    ""
+                };
+
+                match time_attack.kind {
+                    TimeAttackKind::SingleBoard => format!(
+                        "Moves: {}   Time: {}{}",
+                        game.number_of_clicks, time, paused
+                    ),
+                    TimeAttackKind::Marathon => format!(
+                        "Boards: {}   Time: {}{}",
+                        time_attack.boards_cleared, time, paused
+                    ),
+                }
+            }
+
+            Mode::Daily(daily) => {
+                let best = daily.best.map_or("-".to_string(), |best| best.to_string());
+                format!(
+                    "{}   Moves: {}   Par: {}   Best: {}",
+                    daily.date, game.number_of_clicks, daily.par, best
+                )
+            }
+
+            Mode::Campaign(campaign) => {
+                let completed = if campaign.is_completed(campaign.level) {
+                    " (completed)"
+                } else {
+                    ""
+                };
+                format!(
+                    "Level {}/{}{}   Moves: {}/{}",
+                    campaign.level + 1,
+                    LEVELS.len(),
+                    completed,
+                    game.number_of_clicks,
+                    game.allowed_clicks
+                )
+            }
+
+            Mode::Zen(zen) => {
+                let average = zen
+                    .average_moves()
+                    .map_or("-".to_string(), |average| format!("{:.1}", average));
+                format!(
+                    "Moves: {}   Boards: {}   Average: {}",
+                    game.number_of_clicks, zen.boards_cleared, average
+                )
+            }
+
+            Mode::ExactPar(_) => {
+                // Should the solver have given up, the budget is merely an upper bound.
+                let (budget, verdict) = match game.optimum {
+                    Some(_) => ("optimum", "   Optimal!"),
+                    None => ("best known", "   Matched!"),
+                };
+                let verdict = match game.state {
+                    GameState::Solved if game.number_of_clicks <= game.allowed_clicks => verdict,
+                    _ => "",
+                };
+                format!(
+                    "Moves: {}/{} ({}){}",
+                    game.number_of_clicks, game.allowed_clicks, budget, verdict
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn result(
+        kind: TimeAttackKind,
+        boards_cleared: u32,
+        time: f64,
+        moves: u32,
+    ) -> TimeAttackResult {
+        TimeAttackResult {
+            kind,
+            limit: 120.0,
+            boards_cleared,
+            time,
+            moves,
+        }
+    }
+
+    #[test]
+    fn should_keep_best_time_attack_result() {
+        let results = TimeAttackResults {
+            result: vec![
+                result(TimeAttackKind::SingleBoard, 1, 80.0, 20),
+                result(TimeAttackKind::SingleBoard, 0, 120.0, 15),
+                result(TimeAttackKind::SingleBoard, 1, 60.0, 25),
+                result(TimeAttackKind::Marathon, 3, 120.0, 70),
+                result(TimeAttackKind::Marathon, 4, 120.0, 90),
+                result(TimeAttackKind::Marathon, 4, 120.0, 80),
+            ],
+        };
+
+        let single_board = results.best(TimeAttackKind::SingleBoard, 120.0).unwrap();
+        let marathon = results.best(TimeAttackKind::Marathon, 120.0).unwrap();
+
+        assert_eq!(single_board.time, 60.0);
+        assert_eq!((marathon.boards_cleared, marathon.moves), (4, 80));
+        assert_eq!(results.best(TimeAttackKind::Marathon, 300.0), None);
+    }
+
+    #[test]
+    fn should_have_no_average_before_the_first_board() {
+        let zen = Zen::default();
+
+        assert_eq!(zen.average_moves(), None);
+    }
+
+    #[test]
+    fn should_average_moves_over_all_boards() {
+        let mut zen = Zen::default();
+
+        zen.record(10);
+        zen.record(15);
+
+        assert_eq!(zen.boards_cleared, 2);
+        assert_eq!(zen.average_moves(), Some(12.5));
+    }
+
+    #[test]
+    fn should_allow_exactly_the_optimal_number_of_moves() {
+        let settings = Settings {
+            size: 6,
+            number_of_colours: 4,
+            ..Settings::default()
+        };
+        let game = ExactPar::new(0).create_game(&settings);
+
+        let solution = solver::solve(&game.graph, &game.start, solver::DEFAULT_SEARCH_LIMIT);
+
+        assert!(solution.optimal);
+        assert_eq!(game.allowed_clicks, solution.moves.len() as u32);
+    }
+
+    #[test]
+    fn should_try_different_boards_with_a_seed() {
+        let settings = Settings {
+            size: 4,
+            number_of_colours: 3,
+            seed: Some(7),
+            ..Settings::default()
+        };
+        let game = ExactPar::new(u32::MAX).create_game(&settings);
+
+        // No board is that hard, so the hardest of all attempts is kept, which is not the first.
+        let mut rng = settings.rng();
+        let pars: Vec<u32> = (0..MAXIMUM_ATTEMPTS)
+            .map(|_| {
+                let game = Game::create_with(&settings, &mut rng);
+                solver::solve(&game.graph, &game.start, solver::DEFAULT_SEARCH_LIMIT)
+                    .moves
+                    .len() as u32
+            })
+            .collect();
+        let hardest = pars.iter().copied().max();
+
+        assert_ne!(Some(pars[0]), hardest);
+        assert_eq!(Some(game.allowed_clicks), hardest);
+        assert_eq!(game.optimum, hardest);
+    }
+}
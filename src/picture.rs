@@ -0,0 +1,288 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::colour::{Colour, Palette};
+use crate::generator;
+use crate::grid::Grid;
+use crate::topology::Topology;
+
+/// Red, green and blue components of a pixel.
+type Rgb = [f32; 3];
+
+/// The 4x4 Bayer matrix for ordered dithering.
+const BAYER_MATRIX: [[f32; 4]; 4] = [
+    [0.0, 8.0, 2.0, 10.0],
+    [12.0, 4.0, 14.0, 6.0],
+    [3.0, 11.0, 1.0, 9.0],
+    [15.0, 7.0, 13.0, 5.0],
+];
+
+/// How far ordered dithering moves a pixel at most, in each component.
+const ORDERED_DITHERING_SPREAD: f32 = 64.0;
+
+/// How to hide the difference between the colours of a picture and the few colours of a board.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Dithering {
+    /// Every cell gets the colour closest to it.
+    None,
+
+    /// The difference is passed on to the cells to the right and below.
+    FloydSteinberg,
+
+    /// Cells are made lighter or darker following a fixed pattern.
+    Ordered,
+}
+
+impl Dithering {
+    pub const NAMES: [&'static str; 3] = ["none", "floyd-steinberg", "ordered"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(Dithering::None),
+            "floyd-steinberg" => Some(Dithering::FloydSteinberg),
+            "ordered" => Some(Dithering::Ordered),
+            _ => None,
+        }
+    }
+}
+
+fn rgb(palette: &Palette, colour: Colour) -> Rgb {
+    let (r, g, b) = palette.rgb(colour);
+    [r as f32, g as f32, b as f32]
+}
+
+fn distance(a: Rgb, b: Rgb) -> f32 {
+    (0..3).map(|i| (a[i] - b[i]).powi(2)).sum()
+}
+
+/// The one of `colours` closest to `pixel`.
+fn closest(palette: &Palette, colours: &[Colour], pixel: Rgb) -> Colour {
+    *colours
+        .iter()
+        .min_by(|&&a, &&b| {
+            distance(rgb(palette, a), pixel).total_cmp(&distance(rgb(palette, b), pixel))
+        })
+        .unwrap()
+}
+
+/// An image to turn into a board.
+#[derive(Debug)]
+pub struct Picture {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgb>,
+}
+
+impl Picture {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let file = File::open(path)
+            .map_err(|error| format!("Cannot open {}: {}", path.display(), error))?;
+
+        Self::decode(file).map_err(|error| format!("Cannot load {}: {}", path.display(), error))
+    }
+
+    /// Read a PNG image.  Transparent parts of the image are shown in front of black.
+    pub fn decode(reader: impl Read) -> Result<Self, png::DecodingError> {
+        let mut decoder = png::Decoder::new(reader);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+
+        let channels = info.color_type.samples();
+        let pixels = buffer[..info.buffer_size()]
+            .chunks_exact(channels)
+            .map(|pixel| {
+                let (colour, alpha) = match pixel {
+                    [grey] => ([*grey; 3], 255),
+                    [grey, alpha] => ([*grey; 3], *alpha),
+                    [r, g, b] => ([*r, *g, *b], 255),
+                    [r, g, b, alpha, ..] => ([*r, *g, *b], *alpha),
+                    [] => unreachable!(),
+                };
+                colour.map(|component| component as f32 * alpha as f32 / 255.0)
+            })
+            .collect();
+
+        Ok(Self {
+            width: info.width as usize,
+            height: info.height as usize,
+            pixels,
+        })
+    }
+
+    /// The average colour of the pixels under every cell when the picture is stretched to cover
+    /// the whole board.
+    fn downsample(&self, topology: &dyn Topology) -> Vec<Rgb> {
+        let (width, height) = topology.extent();
+        let scale_x = self.width as f32 / width;
+        let scale_y = self.height as f32 / height;
+
+        (0..topology.number_of_cells())
+            .map(|cell| {
+                let polygon = topology.polygon(cell);
+                let (left, right, top, bottom) = polygon.iter().fold(
+                    (f32::MAX, f32::MIN, f32::MAX, f32::MIN),
+                    |(left, right, top, bottom), &(x, y)| {
+                        (left.min(x), right.max(x), top.min(y), bottom.max(y))
+                    },
+                );
+
+                // Every cell covers at least one pixel, even if the picture is tiny.
+                let first_column = ((left * scale_x) as usize).min(self.width - 1);
+                let last_column =
+                    ((right * scale_x).ceil() as usize).clamp(first_column + 1, self.width);
+                let first_row = ((top * scale_y) as usize).min(self.height - 1);
+                let last_row =
+                    ((bottom * scale_y).ceil() as usize).clamp(first_row + 1, self.height);
+
+                let mut sum = [0.0; 3];
+                for row in first_row..last_row {
+                    for column in first_column..last_column {
+                        let pixel = self.pixels[row * self.width + column];
+                        for i in 0..3 {
+                            sum[i] += pixel[i];
+                        }
+                    }
+                }
+                let count = ((last_row - first_row) * (last_column - first_column)) as f32;
+                sum.map(|component| component / count)
+            })
+            .collect()
+    }
+
+    /// A board showing the picture in the `number_of_colours` colours of `palette` that suit it
+    /// best.
+    pub fn grid(
+        &self,
+        topology: Rc<dyn Topology>,
+        palette: &Palette,
+        number_of_colours: u32,
+        dithering: Dithering,
+    ) -> Grid {
+        let mut pixels = self.downsample(&*topology);
+        // Boards always use the first colours of the palette, like generated ones, so that the
+        // colours keep their keys and switching palettes recolours the picture consistently.
+        let colours = generator::palette(number_of_colours);
+        let columns = topology.number_of_columns();
+
+        let mut cells = Vec::with_capacity(pixels.len());
+        for cell in 0..pixels.len() {
+            let (row, column) = (cell / columns, cell % columns);
+            let pixel = match dithering {
+                Dithering::Ordered => {
+                    let offset = (BAYER_MATRIX[row % 4][column % 4] + 0.5) / 16.0 - 0.5;
+                    pixels[cell].map(|component| component + offset * ORDERED_DITHERING_SPREAD)
+                }
+                Dithering::None | Dithering::FloydSteinberg => pixels[cell],
+            };
+            let colour = closest(palette, &colours, pixel);
+            cells.push(colour);
+
+            if dithering == Dithering::FloydSteinberg {
+                let target = rgb(palette, colour);
+                let error: Rgb = [0, 1, 2].map(|i| pixel[i] - target[i]);
+                let diffuse = [(0, 1, 7.0), (1, -1, 3.0), (1, 0, 5.0), (1, 1, 1.0)];
+                for (dr, dc, weight) in diffuse {
+                    let (r, c) = (row + dr, column as isize + dc);
+                    if r * columns < pixels.len() && c >= 0 && (c as usize) < columns {
+                        let neighbour = &mut pixels[r * columns + c as usize];
+                        for i in 0..3 {
+                            neighbour[i] += error[i] * weight / 16.0;
+                        }
+                    }
+                }
+            }
+        }
+
+        Grid::new(topology, cells)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::topology::TopologyKind;
+
+    /// A PNG image of the given size with the left half red and the right half blue.
+    fn red_and_blue(width: u32, height: u32) -> Vec<u8> {
+        let mut data = vec![];
+        for _ in 0..height {
+            for x in 0..width {
+                data.extend(if x < width / 2 {
+                    [255, 0, 0]
+                } else {
+                    [0, 0, 255]
+                });
+            }
+        }
+
+        let mut bytes = vec![];
+        let mut encoder = png::Encoder::new(&mut bytes, width, height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .unwrap()
+            .write_image_data(&data)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn should_downsample_picture_to_board() {
+        let picture = Picture::decode(&red_and_blue(40, 30)[..]).unwrap();
+
+        let grid = picture.grid(
+            TopologyKind::Square.create(3, 4),
+            &Palette::default(),
+            7,
+            Dithering::None,
+        );
+
+        for row in 0..3 {
+            assert_eq!(
+                grid.cells[row * 4..row * 4 + 4],
+                [Colour::RED, Colour::RED, Colour::BLUE, Colour::BLUE]
+            );
+        }
+    }
+
+    #[test]
+    fn should_only_use_the_first_colours_of_the_palette() {
+        let picture = Picture::decode(&red_and_blue(40, 30)[..]).unwrap();
+
+        // Blue comes seventh in the default palette.
+        let grid = picture.grid(
+            TopologyKind::Square.create(3, 4),
+            &Palette::default(),
+            3,
+            Dithering::FloydSteinberg,
+        );
+
+        assert!(grid.cells.iter().all(|colour| colour.0 < 3));
+    }
+
+    #[test]
+    fn should_mix_colours_when_dithering() {
+        // Olive lies between green, yellow and red without being close to any of them.
+        let picture = Picture {
+            width: 4,
+            height: 4,
+            pixels: vec![[128.0, 128.0, 0.0]; 16],
+        };
+        let topology = TopologyKind::Square.create(4, 4);
+        let palette = Palette::parse("#ff0000 #ffff00 #00b000").unwrap();
+
+        for dithering in [Dithering::FloydSteinberg, Dithering::Ordered] {
+            let grid = picture.grid(topology.clone(), &palette, 3, dithering);
+            let colours: std::collections::HashSet<_> = grid.cells.iter().collect();
+
+            assert!(colours.len() > 1);
+        }
+        let grid = picture.grid(topology, &palette, 3, Dithering::None);
+        assert!(grid.cells.iter().all(|&colour| colour == grid.cells[0]));
+    }
+}
@@ -0,0 +1,222 @@
+use std::io::{self, BufRead, Write};
+
+use crate::colour::Colour;
+use crate::game::{Game, GameState};
+use crate::graph::Lock;
+use crate::topology::Connectivity;
+
+// A line-based protocol for playing Flood-It from other programs, e.g. bots.  The program writes
+// the state of the board to stdout and reads one move per line from stdin.  Every line starts
+// with a keyword; clients should skip lines with keywords they do not know so that later
+// versions can add information without breaking them.
+//
+// Version 1 looks as follows, with `<...>` standing for numbers:
+//
+//     flood-it 1
+//     size <rows> <columns>
+//     topology square|hex|torus|triangle
+//     connectivity 4|8
+//     colours <colour> <colour> ...
+//     start <row> <column>
+//     board
+//     <one line per row, with the colour of every cell or '.' for a hole, separated by spaces>
+//     lock <row> <column> moves|matches <count>
+//     moves <used> <allowed>
+//
+// `topology` and `connectivity` tell which cells are adjacent, with the same names as on the
+// command line: square cells touch the ones above, below, left and right of them.  On a hex
+// board, odd rows are shifted right by half a cell, so a cell touches two cells in each of the
+// rows above and below it.  A torus is a square board whose opposite edges are glued together.
+// Triangles point up and down in turn, starting with up in even rows and down in odd rows, and
+// touch the cells left and right of them as well as the one below (pointing up) or above
+// (pointing down).  With connectivity 8, cells touching only at a corner are adjacent as well.
+//
+// The `lock` line appears once for every locked cell and not at all on boards without locks.
+// Colours are indices into the palette.  Everything from `board` on is repeated after every move.
+//
+// The client answers with one of the colours of the `colours` line, flooding the region around
+// the start cell with it.  Empty lines and lines starting with `#` are ignored, `quit` ends the
+// game.  Invalid input yields `error <message>` and does not count as a move.
+//
+// The game ends with `solved <used> <allowed>` once the board is flooded, or with
+// `failed <used> <allowed>` once the moves are used up.  The board is solved within the budget
+// if and only if `solved` is followed by a number no larger than the allowed number of moves.
+
+/// The version of the protocol, which changes whenever the output changes in a way that can
+/// confuse existing clients.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Write the current state of the board, i.e. everything from the `board` line on.
+fn write_state(game: &Game, output: &mut impl Write) -> io::Result<()> {
+    let grid = game.grid();
+    let columns = grid.number_of_columns();
+
+    writeln!(output, "board")?;
+    for (row, cells) in grid.cells.chunks(columns).enumerate() {
+        let line: Vec<String> = cells
+            .iter()
+            .enumerate()
+            .map(|(column, colour)| {
+                if grid.is_void(row * columns + column) {
+                    ".".to_string()
+                } else {
+                    colour.0.to_string()
+                }
+            })
+            .collect();
+        writeln!(output, "{}", line.join(" "))?;
+    }
+
+    for (&cell, lock) in &grid.locks {
+        let (kind, count) = match lock {
+            Lock::Moves(count) => ("moves", count),
+            Lock::Matches(count) => ("matches", count),
+        };
+        let position = grid.position(cell);
+        writeln!(
+            output,
+            "lock {} {} {} {}",
+            position.row, position.column, kind, count
+        )?;
+    }
+
+    writeln!(
+        output,
+        "moves {} {}",
+        game.number_of_clicks, game.allowed_clicks
+    )?;
+    output.flush()
+}
+
+/// Play `game` reading moves from `input` and writing the board to `output`, until the board is
+/// flooded, the moves are used up or the input ends.
+pub fn run(game: &mut Game, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    let colours: Vec<String> = game.colours.iter().map(|c| c.0.to_string()).collect();
+
+    writeln!(output, "flood-it {}", PROTOCOL_VERSION)?;
+    writeln!(
+        output,
+        "size {} {}",
+        game.topology.number_of_rows(),
+        game.topology.number_of_columns()
+    )?;
+    writeln!(output, "topology {}", game.topology.name())?;
+    let neighbours = match game.connectivity {
+        Connectivity::Edges => 4,
+        Connectivity::EdgesAndCorners => 8,
+    };
+    writeln!(output, "connectivity {}", neighbours)?;
+    writeln!(output, "colours {}", colours.join(" "))?;
+    writeln!(output, "start {} {}", game.start.row, game.start.column)?;
+    write_state(game, &mut output)?;
+
+    for line in input.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line == "quit" {
+            break;
+        }
+
+        let colour = match line.parse().map(Colour) {
+            Ok(colour) if game.colours.contains(&colour) => colour,
+            _ => {
+                writeln!(output, "error '{}' is not one of the colours", line)?;
+                output.flush()?;
+                continue;
+            }
+        };
+
+        game.fill_component_of_top_left_cell_with(colour);
+        write_state(game, &mut output)?;
+
+        let result = if game.graph.components.len() == 1 {
+            game.state = GameState::Solved;
+            "solved"
+        } else if game.number_of_clicks >= game.allowed_clicks {
+            "failed"
+        } else {
+            continue;
+        };
+        writeln!(
+            output,
+            "{} {} {}",
+            result, game.number_of_clicks, game.allowed_clicks
+        )?;
+        break;
+    }
+
+    output.flush()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::grid::Grid;
+    use crate::topology::TopologyKind;
+
+    fn play(cells: Vec<Colour>, allowed_clicks: u32, input: &str) -> String {
+        let grid = Grid::new(TopologyKind::Square.create(2, 2), cells);
+        let mut game = Game::from_grid(&grid, allowed_clicks);
+        let mut output = vec![];
+
+        run(&mut game, input.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn should_describe_board_and_report_solution() {
+        let (red, yellow) = (Colour::RED, Colour::YELLOW);
+        let output = play(vec![red, yellow, yellow, red], 3, "# first move\n1\n\n0\n");
+
+        assert_eq!(
+            output,
+            "flood-it 1\nsize 2 2\ntopology square\nconnectivity 4\n\
+             colours 0 1\nstart 0 0\n\
+             board\n0 1\n1 0\nmoves 0 3\n\
+             board\n1 1\n1 0\nmoves 1 3\n\
+             board\n0 0\n0 0\nmoves 2 3\n\
+             solved 2 3\n"
+        );
+    }
+
+    #[test]
+    fn should_reject_colours_not_on_board() {
+        let (red, yellow) = (Colour::RED, Colour::YELLOW);
+        let output = play(vec![red, yellow, yellow, red], 3, "7\nblue\nquit\n1\n");
+
+        assert!(output.contains("error '7' is not one of the colours\n"));
+        assert!(output.contains("error 'blue' is not one of the colours\n"));
+        assert!(output.ends_with(concat!(
+            "moves 0 3\n",
+            "error '7' is not one of the colours\n",
+            "error 'blue' is not one of the colours\n",
+        )));
+    }
+
+    #[test]
+    fn should_fail_once_moves_are_used_up() {
+        let (red, yellow) = (Colour::RED, Colour::YELLOW);
+        let output = play(vec![red, yellow, yellow, red], 1, "1\n0\n");
+
+        assert!(output.ends_with("failed 1 1\n"));
+    }
+
+    #[test]
+    fn should_describe_board_shape() {
+        let grid = Grid::new(
+            TopologyKind::Triangular.create(2, 2),
+            vec![Colour::RED, Colour::YELLOW, Colour::YELLOW, Colour::RED],
+        )
+        .with_connectivity(Connectivity::EdgesAndCorners);
+        let mut game = Game::from_grid(&grid, 3);
+        let mut output = vec![];
+
+        run(&mut game, "quit\n".as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.contains("\ntopology triangle\nconnectivity 8\n"));
+    }
+}
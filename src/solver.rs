@@ -0,0 +1,445 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+
+use crate::colour::Colour;
+use crate::graph::{Graph, Position};
+
+// Finding the shortest solution of a Flood-It board is NP-hard, so we cannot hope to solve large
+// boards optimally in general.  Luckily, an A* search over the graph of connected components
+// copes well with the board sizes people actually play.  The search keeps track of the set of
+// components that have been flooded so far.  The colour of the flooded region is irrelevant for
+// what the next move can absorb: all neighbouring components of the flood's own colour have
+// already been absorbed by the move that gave it that colour.
+//
+// The heuristic combines two observations about the moves still required.  Every move removes at
+// most one colour from the rest of the board, and every move extends the flood by at most one
+// layer of components.  See `Problem::lower_bound` for the details.
+//
+// When a move would remove a colour from the board entirely, making that move right away never
+// hurts.  This prunes the search considerably towards the end of a game.
+
+/// The number of states the optimal search may expand before it gives up.
+pub const DEFAULT_SEARCH_LIMIT: usize = 100_000;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Solution {
+    /// The colours to choose, one per move.
+    pub moves: Vec<Colour>,
+
+    /// Whether there is provably no shorter solution.
+    pub optimal: bool,
+}
+
+/// A set of component indices.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+struct BitSet {
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    fn new(size: usize) -> Self {
+        Self {
+            words: vec![0; size.div_ceil(64)],
+        }
+    }
+
+    fn insert(&mut self, i: usize) {
+        self.words[i / 64] |= 1 << (i % 64);
+    }
+
+    fn contains(&self, i: usize) -> bool {
+        self.words[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    fn len(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+}
+
+/// A compact copy of the component graph which is cheap to search.  Colours are numbered densely
+/// so they can be used as indices.
+struct Problem {
+    palette: Vec<Colour>,
+    colours: Vec<usize>,
+    sizes: Vec<usize>,
+    neighbours: Vec<Vec<usize>>,
+    start: usize,
+}
+
+impl Problem {
+    fn create(graph: &Graph, start: &Position) -> Self {
+        let mut ids: Vec<usize> = graph.components.keys().copied().collect();
+        ids.sort_unstable();
+        let index: HashMap<usize, usize> = ids.iter().enumerate().map(|(i, &id)| (id, i)).collect();
+
+        let mut palette = vec![];
+        let mut colours = Vec::with_capacity(ids.len());
+        for id in &ids {
+            let colour = graph.components[id].colour;
+            let colour_index = match palette.iter().position(|&c| c == colour) {
+                Some(colour_index) => colour_index,
+                None => {
+                    palette.push(colour);
+                    palette.len() - 1
+                }
+            };
+            colours.push(colour_index);
+        }
+
+        let sizes = ids
+            .iter()
+            .map(|id| graph.components[id].cells.len())
+            .collect();
+        let neighbours = ids
+            .iter()
+            .map(|id| {
+                let mut neighbours: Vec<usize> =
+                    graph.neighbours[id].iter().map(|n| index[n]).collect();
+                neighbours.sort_unstable();
+                neighbours
+            })
+            .collect();
+
+        Self {
+            palette,
+            colours,
+            sizes,
+            neighbours,
+            start: index[&graph.find_component(start).id],
+        }
+    }
+
+    fn number_of_components(&self) -> usize {
+        self.colours.len()
+    }
+
+    fn initial_flood(&self) -> BitSet {
+        let mut flood = BitSet::new(self.number_of_components());
+        flood.insert(self.start);
+        flood
+    }
+
+    /// The components which are not flooded yet but touch the flood.
+    fn frontier(&self, flood: &BitSet) -> Vec<usize> {
+        let mut seen = BitSet::new(self.number_of_components());
+        let mut frontier = vec![];
+
+        for i in (0..self.number_of_components()).filter(|&i| flood.contains(i)) {
+            for &n in &self.neighbours[i] {
+                if !flood.contains(n) && !seen.contains(n) {
+                    seen.insert(n);
+                    frontier.push(n);
+                }
+            }
+        }
+
+        frontier
+    }
+
+    /// The colours that can be chosen in a sensible move, in a fixed order.
+    fn candidate_colours(&self, frontier: &[usize]) -> Vec<usize> {
+        let mut colours = vec![];
+        for &i in frontier {
+            if !colours.contains(&self.colours[i]) {
+                colours.push(self.colours[i]);
+            }
+        }
+        colours
+    }
+
+    fn flood_with(&self, flood: &BitSet, frontier: &[usize], colour: usize) -> BitSet {
+        let mut result = flood.clone();
+        for &i in frontier {
+            if self.colours[i] == colour {
+                result.insert(i);
+            }
+        }
+        result
+    }
+
+    fn is_solved(&self, flood: &BitSet) -> bool {
+        flood.len() == self.number_of_components()
+    }
+
+    /// A colour whose components all touch the flood, if there is one, the first in the palette
+    /// if there are several.
+    fn colour_to_eliminate(&self, flood: &BitSet, frontier: &[usize]) -> Option<usize> {
+        let mut remaining = vec![0; self.palette.len()];
+        for i in (0..self.number_of_components()).filter(|&i| !flood.contains(i)) {
+            remaining[self.colours[i]] += 1;
+        }
+        for &i in frontier {
+            remaining[self.colours[i]] -= 1;
+        }
+
+        self.candidate_colours(frontier)
+            .into_iter()
+            .filter(|&colour| remaining[colour] == 0)
+            .min_by_key(|&colour| self.palette[colour])
+    }
+
+    /// A lower bound on the number of moves needed to flood the whole board.
+    ///
+    /// A component at distance `k` from the flood cannot be absorbed in fewer than `k` moves.
+    /// Hence, none of the colours occurring at a distance of at least `k` can disappear from the
+    /// board during the first `k - 1` moves, and afterwards each of them needs a move of its own.
+    /// For `k = 1` this is the number of remaining colours, for the largest distance it is at
+    /// least that distance.
+    fn lower_bound(&self, flood: &BitSet) -> usize {
+        let mut distance = vec![usize::MAX; self.number_of_components()];
+        let mut queue = VecDeque::new();
+        for i in (0..self.number_of_components()).filter(|&i| flood.contains(i)) {
+            distance[i] = 0;
+            queue.push_back(i);
+        }
+
+        // The largest distance at which each colour occurs, if it does at all.
+        let mut farthest = vec![0; self.palette.len()];
+        while let Some(i) = queue.pop_front() {
+            farthest[self.colours[i]] = farthest[self.colours[i]].max(distance[i]);
+            for &n in &self.neighbours[i] {
+                if distance[n] == usize::MAX {
+                    distance[n] = distance[i] + 1;
+                    queue.push_back(n);
+                }
+            }
+        }
+
+        let eccentricity = farthest.iter().copied().max().unwrap_or(0);
+        (1..=eccentricity)
+            .map(|k| k - 1 + farthest.iter().filter(|&&d| d >= k).count())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The number of cells absorbed by flooding with `colour`.
+    fn gain(&self, frontier: &[usize], colour: usize) -> usize {
+        frontier
+            .iter()
+            .filter(|&&i| self.colours[i] == colour)
+            .map(|&i| self.sizes[i])
+            .sum()
+    }
+
+    /// The move absorbing the largest number of cells, preferring moves which remove a colour
+    /// from the board entirely.  Ties go to the first colour in the palette, so that the move does
+    /// not depend on how the components happen to be numbered.
+    fn greedy_move(&self, flood: &BitSet) -> Option<usize> {
+        let frontier = self.frontier(flood);
+
+        self.colour_to_eliminate(flood, &frontier).or_else(|| {
+            let mut colours = self.candidate_colours(&frontier);
+            colours.sort_by_key(|&colour| self.palette[colour]);
+
+            let mut best = None;
+            for colour in colours {
+                let gain = self.gain(&frontier, colour);
+                if best.is_none_or(|(_, best_gain)| gain > best_gain) {
+                    best = Some((colour, gain));
+                }
+            }
+            best.map(|(colour, _)| colour)
+        })
+    }
+
+    fn greedy(&self) -> Vec<Colour> {
+        let mut flood = self.initial_flood();
+        let mut moves = vec![];
+
+        while let Some(colour) = self.greedy_move(&flood) {
+            let frontier = self.frontier(&flood);
+            flood = self.flood_with(&flood, &frontier, colour);
+            moves.push(self.palette[colour]);
+        }
+
+        moves
+    }
+
+    fn a_star(&self, limit: usize) -> Option<Vec<Colour>> {
+        let initial = self.initial_flood();
+
+        let mut states = vec![initial.clone()];
+        let mut predecessors: Vec<Option<(usize, usize)>> = vec![None];
+        let mut best_distance: HashMap<BitSet, usize> = HashMap::new();
+        best_distance.insert(initial.clone(), 0);
+
+        // Among states with the same estimate, expand those closest to a solution first.
+        let mut queue = BinaryHeap::new();
+        queue.push(Reverse((self.lower_bound(&initial), Reverse(0), 0)));
+
+        let mut expanded = 0;
+        while let Some(Reverse((_, Reverse(distance), state))) = queue.pop() {
+            let flood = states[state].clone();
+            if best_distance[&flood] < distance {
+                continue;
+            }
+
+            if self.is_solved(&flood) {
+                let mut moves = vec![];
+                let mut current = state;
+                while let Some((previous, colour)) = predecessors[current] {
+                    moves.push(self.palette[colour]);
+                    current = previous;
+                }
+                moves.reverse();
+                return Some(moves);
+            }
+
+            expanded += 1;
+            if expanded > limit {
+                return None;
+            }
+
+            let frontier = self.frontier(&flood);
+            let colours = match self.colour_to_eliminate(&flood, &frontier) {
+                Some(colour) => vec![colour],
+                None => self.candidate_colours(&frontier),
+            };
+
+            for colour in colours {
+                let next = self.flood_with(&flood, &frontier, colour);
+                let next_distance = distance + 1;
+                if best_distance
+                    .get(&next)
+                    .is_some_and(|&d| d <= next_distance)
+                {
+                    continue;
+                }
+
+                best_distance.insert(next.clone(), next_distance);
+                let estimate = next_distance + self.lower_bound(&next);
+                states.push(next);
+                predecessors.push(Some((state, colour)));
+                queue.push(Reverse((
+                    estimate,
+                    Reverse(next_distance),
+                    states.len() - 1,
+                )));
+            }
+        }
+
+        unreachable!()
+    }
+}
+
+/// The move the greedy solution starts with, or `None` if the board is flooded already.  Bots use
+/// this to play greedily one move at a time.
+pub fn greedy_move(graph: &Graph, start: &Position) -> Option<Colour> {
+    let problem = Problem::create(graph, start);
+
+    problem
+        .greedy_move(&problem.initial_flood())
+        .map(|colour| problem.palette[colour])
+}
+
+/// Search for a shortest solution, expanding at most `limit` states.  If the search gives up, the
+/// greedy solution is returned instead.
+pub fn solve(graph: &Graph, start: &Position, limit: usize) -> Solution {
+    let problem = Problem::create(graph, start);
+
+    match problem.a_star(limit) {
+        Some(moves) => Solution {
+            moves,
+            optimal: true,
+        },
+        None => Solution {
+            moves: problem.greedy(),
+            optimal: false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::game::TOP_LEFT_CELL;
+    use crate::grid::Grid;
+    use crate::topology::TopologyKind;
+
+    fn graph(columns: usize, rows: usize, cells: Vec<Colour>) -> Graph {
+        Graph::create(&Grid::new(
+            TopologyKind::Square.create(rows, columns),
+            cells,
+        ))
+    }
+
+    fn apply(graph: &mut Graph, moves: &[Colour]) {
+        for &colour in moves {
+            graph.change_colour_of_component_at(&TOP_LEFT_CELL, colour);
+        }
+    }
+
+    #[test]
+    fn should_need_no_moves_for_a_flooded_board() {
+        let graph = graph(2, 2, vec![Colour::RED; 4]);
+
+        let solution = solve(&graph, &TOP_LEFT_CELL, DEFAULT_SEARCH_LIMIT);
+
+        assert_eq!(solution.moves, vec![]);
+        assert!(solution.optimal);
+    }
+
+    #[test]
+    fn should_find_shortest_solution() {
+        let (red, yellow, green) = (Colour::RED, Colour::YELLOW, Colour::GREEN);
+        #[rustfmt::skip]
+        let graph = graph(4, 2, vec![
+            red, yellow, yellow, green,
+            green, red, yellow, red,
+        ]);
+
+        let greedy_moves = Problem::create(&graph, &TOP_LEFT_CELL).greedy();
+        let solution = solve(&graph, &TOP_LEFT_CELL, DEFAULT_SEARCH_LIMIT);
+
+        assert!(solution.optimal);
+        assert_eq!(solution.moves.len(), 3);
+        assert!(greedy_moves.len() >= solution.moves.len());
+    }
+
+    #[test]
+    fn should_flood_the_board_with_the_solution() {
+        let mut graph = Graph::create(&Grid::generate(6, 4));
+
+        let solution = solve(&graph, &TOP_LEFT_CELL, DEFAULT_SEARCH_LIMIT);
+        apply(&mut graph, &solution.moves);
+
+        assert_eq!(graph.components.len(), 1);
+    }
+
+    #[test]
+    fn should_flood_the_board_greedily() {
+        let mut graph = Graph::create(&Grid::generate(8, 6));
+
+        let moves = Problem::create(&graph, &TOP_LEFT_CELL).greedy();
+        apply(&mut graph, &moves);
+
+        assert_eq!(graph.components.len(), 1);
+    }
+
+    #[test]
+    fn should_fall_back_to_greedy_solution() {
+        let graph = Graph::create(&Grid::generate(8, 6));
+
+        let solution = solve(&graph, &TOP_LEFT_CELL, 0);
+
+        assert!(!solution.optimal);
+        assert_eq!(
+            solution.moves,
+            Problem::create(&graph, &TOP_LEFT_CELL).greedy()
+        );
+    }
+
+    #[test]
+    fn should_play_the_greedy_solution_move_by_move() {
+        let mut graph = Graph::create(&Grid::generate(8, 6));
+        let solution = Problem::create(&graph, &TOP_LEFT_CELL).greedy();
+
+        let mut moves = vec![];
+        while let Some(colour) = greedy_move(&graph, &TOP_LEFT_CELL) {
+            graph.change_colour_of_component_at(&TOP_LEFT_CELL, colour);
+            moves.push(colour);
+        }
+
+        assert_eq!(moves, solution);
+    }
+}
@@ -0,0 +1,341 @@
+use std::cmp::Reverse;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::Game;
+use crate::storage;
+use crate::topology::Connectivity;
+
+const STATISTICS_FILE: &str = "statistics.toml";
+
+/// The headings of the columns of `Statistics::rows`.
+pub const COLUMNS: [&str; 10] = [
+    "Mode", "Board", "Size", "Colors", "Played", "Won", "Streak", "Best", "Average", "Optimum",
+];
+
+/// Explains the figures in brackets and the optimum column, to be shown below the table.
+pub const LEGEND: &str = "Budget in brackets; optimum as best / average moves beyond it";
+
+/// The results of all games in one mode on one kind of board: its shape, size and number of
+/// colours.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Tally {
+    pub mode: String,
+    pub topology: String,
+
+    /// Whether cells touching only at a corner are connected.
+    pub corners: bool,
+    pub rows: usize,
+    pub columns: usize,
+    pub colours: usize,
+
+    pub games: u32,
+
+    /// Games which were won or lost, i.e. all of them in modes which tell the two apart.
+    pub decided: u32,
+    pub wins: u32,
+
+    /// Wins in a row up to the last game.
+    pub streak: u32,
+    pub longest_streak: u32,
+
+    /// Games whose board was flooded completely, the only ones whose moves count.
+    pub flooded: u32,
+    pub best_moves: Option<u32>,
+    pub total_moves: u64,
+
+    /// Moves beyond the budget, which is negative for moves to spare.  Modes without a budget
+    /// leave these alone.
+    pub best_versus_budget: Option<i64>,
+    pub total_versus_budget: i64,
+
+    /// Flooded games whose optimum is known, e.g. in exact mode.
+    pub optimum_known: u32,
+    pub best_versus_optimum: Option<u32>,
+    pub total_versus_optimum: u64,
+}
+
+impl Tally {
+    fn is_for(&self, mode: &str, game: &Game) -> bool {
+        self.mode == mode
+            && self.topology == game.topology.name()
+            && self.corners == (game.connectivity == Connectivity::EdgesAndCorners)
+            && self.rows == game.topology.number_of_rows()
+            && self.columns == game.topology.number_of_columns()
+            && self.colours == game.colours.len()
+    }
+
+    /// Count the result of `game`, which is over.  `won` is `None` in modes which do not tell
+    /// winning from losing, and `budget` in modes without a move budget.
+    fn add(&mut self, game: &Game, won: Option<bool>, budget: Option<u32>) {
+        self.games += 1;
+        match won {
+            Some(true) => {
+                self.decided += 1;
+                self.wins += 1;
+                self.streak += 1;
+                self.longest_streak = self.longest_streak.max(self.streak);
+            }
+            Some(false) => {
+                self.decided += 1;
+                self.streak = 0;
+            }
+            None => (),
+        }
+
+        // Running out of time or giving up leaves boards unfinished, which says nothing about the
+        // moves.
+        if game.graph.components.len() != 1 {
+            return;
+        }
+
+        let moves = game.number_of_clicks;
+        self.flooded += 1;
+        self.best_moves = Some(self.best_moves.map_or(moves, |best| best.min(moves)));
+        self.total_moves += moves as u64;
+
+        if let Some(budget) = budget {
+            let versus_budget = moves as i64 - budget as i64;
+            self.best_versus_budget = Some(
+                self.best_versus_budget
+                    .map_or(versus_budget, |best| best.min(versus_budget)),
+            );
+            self.total_versus_budget += versus_budget;
+        }
+
+        if let Some(optimum) = game.optimum {
+            let versus_optimum = moves.saturating_sub(optimum);
+            self.optimum_known += 1;
+            self.best_versus_optimum = Some(
+                self.best_versus_optimum
+                    .map_or(versus_optimum, |best| best.min(versus_optimum)),
+            );
+            self.total_versus_optimum += versus_optimum as u64;
+        }
+    }
+
+    /// The tally as a row of the statistics table, see `COLUMNS`.
+    fn row(&self) -> Vec<String> {
+        let average = |total: f64, count: u32| total / count.max(1) as f64;
+
+        let (won, streak) = if self.decided == 0 {
+            ("-".to_string(), "-".to_string())
+        } else {
+            (
+                format!(
+                    "{} ({:.0}%)",
+                    self.wins,
+                    100.0 * average(self.wins as f64, self.decided)
+                ),
+                format!("{} / {}", self.streak, self.longest_streak),
+            )
+        };
+        let (best, mean, optimum) = if self.flooded == 0 {
+            ("-".to_string(), "-".to_string(), "-".to_string())
+        } else {
+            let best_moves = self.best_moves.unwrap_or(0);
+            let average_moves = average(self.total_moves as f64, self.flooded);
+            let (best, mean) = match self.best_versus_budget {
+                Some(versus_budget) => (
+                    format!("{} ({:+})", best_moves, versus_budget),
+                    format!(
+                        "{:.1} ({:+.1})",
+                        average_moves,
+                        average(self.total_versus_budget as f64, self.flooded)
+                    ),
+                ),
+                None => (best_moves.to_string(), format!("{:.1}", average_moves)),
+            };
+            let optimum = match self.best_versus_optimum {
+                Some(best) => format!(
+                    "+{} / +{:.1}",
+                    best,
+                    average(self.total_versus_optimum as f64, self.optimum_known)
+                ),
+                None => "-".to_string(),
+            };
+            (best, mean, optimum)
+        };
+
+        let board = if self.corners {
+            format!("{}, corners", self.topology)
+        } else {
+            self.topology.clone()
+        };
+
+        vec![
+            self.mode.clone(),
+            board,
+            format!("{}x{}", self.rows, self.columns),
+            self.colours.to_string(),
+            self.games.to_string(),
+            won,
+            streak,
+            best,
+            mean,
+            optimum,
+        ]
+    }
+}
+
+/// The results of all games ever played, kept apart by mode and kind of board.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Statistics {
+    #[serde(default)]
+    tally: Vec<Tally>,
+}
+
+impl Statistics {
+    pub fn load() -> Self {
+        storage::load(STATISTICS_FILE)
+    }
+
+    /// Count the result of `game`, which is over, and save the statistics.  See `Tally::add` for
+    /// `won` and `budget`.
+    pub fn record(mode: &str, game: &Game, won: Option<bool>, budget: Option<u32>) {
+        let mut statistics = Self::load();
+        statistics.add(mode, game, won, budget);
+        storage::save(STATISTICS_FILE, &statistics);
+    }
+
+    fn add(&mut self, mode: &str, game: &Game, won: Option<bool>, budget: Option<u32>) {
+        let index = match self.tally.iter().position(|tally| tally.is_for(mode, game)) {
+            Some(index) => index,
+            None => {
+                self.tally.push(Tally {
+                    mode: mode.to_string(),
+                    topology: game.topology.name().to_string(),
+                    corners: game.connectivity == Connectivity::EdgesAndCorners,
+                    rows: game.topology.number_of_rows(),
+                    columns: game.topology.number_of_columns(),
+                    colours: game.colours.len(),
+                    ..Tally::default()
+                });
+                self.tally.len() - 1
+            }
+        };
+
+        self.tally[index].add(game, won, budget);
+    }
+
+    /// One row per kind of game, most played first.  The best and average moves come with the
+    /// difference to the budget in brackets, the optimum column gives the best and average number
+    /// of moves beyond the optimum.
+    pub fn rows(&self) -> Vec<Vec<String>> {
+        let mut tally: Vec<&Tally> = self.tally.iter().collect();
+        tally.sort_by_key(|tally| Reverse(tally.games));
+        tally.into_iter().map(Tally::row).collect()
+    }
+
+    /// The rows below a row of headings, see `COLUMNS`.
+    pub fn table(&self) -> Vec<Vec<String>> {
+        let mut table = vec![COLUMNS.map(String::from).to_vec()];
+        table.extend(self.rows());
+        table
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::colour::Colour;
+    use crate::grid::Grid;
+    use crate::topology::TopologyKind;
+
+    /// A game on a 2x2 board, flooded in `moves` moves if `flooded`.
+    fn game(moves: u32, allowed_clicks: u32, flooded: bool) -> Game {
+        let (red, yellow) = (Colour::RED, Colour::YELLOW);
+        let grid = Grid::new(
+            TopologyKind::Square.create(2, 2),
+            vec![red, yellow, yellow, red],
+        );
+        let mut game = Game::from_grid(&grid, allowed_clicks);
+        if flooded {
+            game.fill_component_of_top_left_cell_with(yellow);
+            game.fill_component_of_top_left_cell_with(red);
+        }
+        game.number_of_clicks = moves;
+        game
+    }
+
+    #[test]
+    fn should_count_wins_and_streaks() {
+        let mut statistics = Statistics::default();
+
+        for won in [true, true, false, true] {
+            statistics.add("classic", &game(3, 3, true), Some(won), Some(3));
+        }
+        statistics.add("classic", &game(1, 3, false), Some(false), Some(3));
+
+        let tally = &statistics.tally[0];
+        assert_eq!(statistics.tally.len(), 1);
+        assert_eq!((tally.games, tally.wins, tally.flooded), (5, 3, 4));
+        assert_eq!((tally.streak, tally.longest_streak), (0, 2));
+    }
+
+    #[test]
+    fn should_compare_moves_with_budget_and_optimum() {
+        let mut statistics = Statistics::default();
+        let mut optimal = game(2, 3, true);
+        optimal.optimum = Some(2);
+
+        statistics.add("exact", &optimal, Some(true), Some(3));
+        statistics.add("exact", &game(5, 3, true), Some(false), Some(3));
+        statistics.add("exact", &game(1, 3, false), Some(false), Some(3));
+
+        let tally = &statistics.tally[0];
+        assert_eq!(tally.flooded, 2);
+        assert_eq!(tally.best_moves, Some(2));
+        assert_eq!(tally.best_versus_budget, Some(-1));
+        assert_eq!(tally.total_versus_budget, 1);
+        assert_eq!(tally.optimum_known, 1);
+        assert_eq!(tally.best_versus_optimum, Some(0));
+        assert_eq!(
+            statistics.rows()[0],
+            vec![
+                "exact",
+                "square",
+                "2x2",
+                "2",
+                "3",
+                "1 (33%)",
+                "0 / 1",
+                "2 (-1)",
+                "3.5 (+0.5)",
+                "+0 / +0.0"
+            ]
+        );
+    }
+
+    #[test]
+    fn should_leave_out_wins_and_budget_without_them() {
+        let mut statistics = Statistics::default();
+
+        statistics.add("zen", &game(3, 3, true), None, None);
+        statistics.add("zen", &game(5, 3, true), None, None);
+
+        assert_eq!(
+            statistics.rows()[0],
+            vec!["zen", "square", "2x2", "2", "2", "-", "-", "3", "4.0", "-"]
+        );
+    }
+
+    #[test]
+    fn should_keep_modes_and_boards_apart() {
+        let mut statistics = Statistics::default();
+        let mut corners = game(3, 3, true);
+        corners.connectivity = Connectivity::EdgesAndCorners;
+
+        statistics.add("classic", &game(3, 3, true), Some(true), Some(3));
+        statistics.add("classic", &corners, Some(true), Some(3));
+        statistics.add("zen", &game(3, 3, true), None, None);
+        statistics.add("zen", &game(3, 3, true), None, None);
+
+        let rows = statistics.rows();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0][0], "zen");
+        assert_eq!(rows[0][4], "2");
+        assert!(rows.iter().any(|row| row[1] == "square, corners"));
+    }
+}
@@ -0,0 +1,52 @@
+use std::fs;
+use std::path::PathBuf;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// The directory in which results are stored, e.g. `~/.local/share/flood-it` on Linux.
+fn data_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("flood-it"))
+}
+
+/// The file `file_name` in the configuration directory, e.g. `~/.config/flood-it` on Linux.
+pub fn config_file(file_name: &str) -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("flood-it").join(file_name))
+}
+
+/// Read `file_name` from the data directory.  Missing or unreadable files yield the default
+/// value so a broken file never keeps anybody from playing.
+pub fn load<T: DeserializeOwned + Default>(file_name: &str) -> T {
+    let Some(path) = data_dir().map(|dir| dir.join(file_name)) else {
+        return T::default();
+    };
+
+    match fs::read_to_string(&path) {
+        Ok(contents) => toml::from_str(&contents).unwrap_or_else(|error| {
+            eprintln!("Ignoring invalid file {}: {}", path.display(), error);
+            T::default()
+        }),
+        Err(_) => T::default(),
+    }
+}
+
+/// Write `value` to `file_name` in the data directory, reporting but otherwise ignoring errors.
+pub fn save<T: Serialize>(file_name: &str, value: &T) {
+    let Some(dir) = data_dir() else {
+        eprintln!("Cannot save {}: no data directory available", file_name);
+        return;
+    };
+
+    let path = dir.join(file_name);
+    let result = toml::to_string(value)
+        .map_err(|error| error.to_string())
+        .and_then(|contents| {
+            fs::create_dir_all(&dir)
+                .and_then(|_| fs::write(&path, contents))
+                .map_err(|error| error.to_string())
+        });
+
+    if let Err(error) = result {
+        eprintln!("Cannot save {}: {}", path.display(), error);
+    }
+}
@@ -0,0 +1,61 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+
+use crate::colour::Colour;
+use crate::game::Game;
+use crate::solver;
+
+/// A way of choosing moves, e.g. for a bot.  Strategies take all their randomness from `rng` so
+/// that results can be reproduced.
+pub trait Strategy {
+    /// A short name for tables and files.
+    fn name(&self) -> &'static str;
+
+    /// The colour to flood with next.  `game` is not solved yet.
+    fn choose(&mut self, game: &Game, rng: &mut StdRng) -> Colour;
+}
+
+/// The colour of the flood, which no strategy should waste a move on.
+fn flood_colour(game: &Game) -> Colour {
+    game.graph.find_component(&game.start).colour
+}
+
+/// Any colour but the one of the flood, chosen at random.
+pub struct Random;
+
+impl Strategy for Random {
+    fn name(&self) -> &'static str {
+        "random"
+    }
+
+    fn choose(&mut self, game: &Game, rng: &mut StdRng) -> Colour {
+        let flood = flood_colour(game);
+        let colours: Vec<Colour> = game
+            .colours
+            .iter()
+            .copied()
+            .filter(|&colour| colour != flood)
+            .collect();
+
+        *colours.choose(rng).unwrap_or(&flood)
+    }
+}
+
+/// The first move of the solver's greedy solution: a colour which disappears from the board, if
+/// there is one, and otherwise the colour which adds the most cells to the flood right away.
+pub struct Greedy;
+
+impl Strategy for Greedy {
+    fn name(&self) -> &'static str {
+        "greedy"
+    }
+
+    fn choose(&mut self, game: &Game, _rng: &mut StdRng) -> Colour {
+        solver::greedy_move(&game.graph, &game.start).unwrap_or_else(|| flood_colour(game))
+    }
+}
+
+/// All strategies the tournament can pit against each other.
+pub fn all() -> Vec<Box<dyn Strategy>> {
+    vec![Box::new(Random), Box::new(Greedy)]
+}
@@ -0,0 +1,210 @@
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+use serde::Deserialize;
+
+use crate::colour::{parse_rgb, Palette, Rgb};
+
+/// What the board looks like apart from the colours of its cells.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub name: String,
+
+    pub background: Rgb,
+
+    /// The palette to switch to along with the theme, if any.
+    pub palette: Option<Rc<Palette>>,
+
+    /// How much of every cell is left empty around its edges, as a share of its size.
+    pub cell_gap: f32,
+
+    /// The radius of the corners of every cell, as a share of its size.
+    pub corner_radius: f32,
+
+    /// The colour of the lines between cells of different components.
+    pub component_borders: Option<Rgb>,
+
+    /// The colour of the line around the flooded region.
+    pub flood_outline: Option<Rgb>,
+}
+
+/// How to draw the board, as chosen on the command line.
+pub struct Appearance {
+    /// The themes to switch between.
+    #[cfg(feature = "gui")]
+    pub themes: Vec<Theme>,
+
+    /// The index of the theme to start with.
+    #[cfg(feature = "gui")]
+    pub theme: usize,
+
+    /// Whether to mark every colour with its own shape.
+    pub show_symbols: bool,
+
+    /// How many cells further a move spreads per second, or zero to show moves at once.
+    #[cfg(feature = "gui")]
+    pub animation_speed: f64,
+}
+
+/// A theme as written in a themes file.  Everything but the name is optional.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThemeDefinition {
+    name: String,
+    background: Option<String>,
+    palette: Option<String>,
+    cell_gap: Option<f32>,
+    corner_radius: Option<f32>,
+    component_borders: Option<String>,
+    flood_outline: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThemesFile {
+    #[serde(default)]
+    theme: Vec<ThemeDefinition>,
+}
+
+fn colour(name: &str, field: &str, text: &str) -> Result<Rgb, String> {
+    parse_rgb(text).ok_or_else(|| format!("Invalid {} '{}' in theme {}.", field, text, name))
+}
+
+impl ThemeDefinition {
+    fn into_theme(self) -> Result<Theme, String> {
+        let name = &self.name;
+        let optional_colour = |field: &str, text: &Option<String>| {
+            text.as_deref()
+                .map(|text| colour(name, field, text))
+                .transpose()
+        };
+
+        // A palette is either the name of a built-in one or a list of colours.
+        let palette = self
+            .palette
+            .as_deref()
+            .map(|text| Palette::from_name(text).map_or_else(|| Palette::parse(text), Ok))
+            .transpose()?
+            .map(Rc::new);
+
+        let cell_gap = self.cell_gap.unwrap_or(0.0);
+        let corner_radius = self.corner_radius.unwrap_or(0.0);
+        if !(0.0..0.5).contains(&cell_gap) || !(0.0..=0.5).contains(&corner_radius) {
+            return Err(format!(
+                "The cell gap and corner radius of theme {} have to lie between 0 and 0.5.",
+                name
+            ));
+        }
+
+        Ok(Theme {
+            background: optional_colour("background", &self.background)?.unwrap_or((0, 0, 0)),
+            palette,
+            cell_gap,
+            corner_radius,
+            component_borders: optional_colour("border color", &self.component_borders)?,
+            flood_outline: optional_colour("outline color", &self.flood_outline)?,
+            name: self.name,
+        })
+    }
+}
+
+impl Theme {
+    /// The themes that are always available, starting with the classic look.
+    pub fn built_in() -> Vec<Self> {
+        let theme = |name: &str| Theme {
+            name: name.to_string(),
+            background: (0, 0, 0),
+            palette: None,
+            cell_gap: 0.0,
+            corner_radius: 0.0,
+            component_borders: None,
+            flood_outline: None,
+        };
+
+        vec![
+            Theme {
+                flood_outline: Some((255, 255, 255)),
+                ..theme("classic")
+            },
+            Theme {
+                component_borders: Some((0, 0, 0)),
+                flood_outline: Some((255, 255, 255)),
+                ..theme("outlined")
+            },
+            Theme {
+                background: (40, 40, 40),
+                cell_gap: 0.06,
+                corner_radius: 0.2,
+                flood_outline: Some((255, 255, 255)),
+                ..theme("tiles")
+            },
+            Theme {
+                background: (255, 255, 255),
+                cell_gap: 0.04,
+                corner_radius: 0.5,
+                flood_outline: Some((0, 0, 0)),
+                ..theme("bubbles")
+            },
+        ]
+    }
+
+    /// Read a themes file, which lists any number of `[[theme]]` tables.
+    pub fn load(path: &Path) -> Result<Vec<Self>, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|error| format!("Cannot read themes {}: {}", path.display(), error))?;
+
+        Self::parse(&text).map_err(|error| format!("Cannot load {}: {}", path.display(), error))
+    }
+
+    pub fn parse(text: &str) -> Result<Vec<Self>, String> {
+        let file: ThemesFile = toml::from_str(text).map_err(|error| error.to_string())?;
+
+        file.theme
+            .into_iter()
+            .map(ThemeDefinition::into_theme)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_parse_themes() {
+        let themes = Theme::parse(
+            r##"
+            [[theme]]
+            name = "night"
+            background = "#101020"
+            palette = "tritanopia"
+            cell_gap = 0.1
+            flood_outline = "#ffffff"
+
+            [[theme]]
+            name = "plain"
+            palette = "#ff0000 #00ff00 #0000ff"
+            "##,
+        )
+        .unwrap();
+
+        assert_eq!(themes.len(), 2);
+        assert_eq!(themes[0].background, (16, 16, 32));
+        assert_eq!(
+            themes[0].palette.as_deref(),
+            Palette::from_name("tritanopia").as_ref()
+        );
+        assert_eq!(themes[0].flood_outline, Some((255, 255, 255)));
+        assert_eq!(themes[0].component_borders, None);
+        assert_eq!(themes[1].palette.as_ref().unwrap().len(), 3);
+        assert_eq!(themes[1].cell_gap, 0.0);
+    }
+
+    #[test]
+    fn should_reject_invalid_themes() {
+        assert!(Theme::parse("[[theme]]\nname = \"a\"\nbackground = \"red\"").is_err());
+        assert!(Theme::parse("[[theme]]\nname = \"a\"\ncell_gap = 0.7").is_err());
+        assert!(Theme::parse("[[theme]]\nname = \"a\"\nshadow = true").is_err());
+    }
+}
@@ -0,0 +1,711 @@
+use std::fmt::Debug;
+use std::rc::Rc;
+
+// A topology describes the shape of a board: which cells there are, which cells touch each other
+// and what each cell looks like.  Cells are numbered row by row, starting at 0, so cell
+// `row * columns + column` is the one at `Position { row, column }`.
+//
+// Board coordinates are chosen such that a square cell is one unit wide.  The UI scales them to
+// fit the window.
+
+/// A point in board coordinates.
+pub type Point = (f32, f32);
+
+pub trait Topology: Debug {
+    /// A short name for the command line and for files.
+    fn name(&self) -> &'static str;
+
+    fn number_of_rows(&self) -> usize;
+
+    fn number_of_columns(&self) -> usize;
+
+    fn number_of_cells(&self) -> usize {
+        self.number_of_rows() * self.number_of_columns()
+    }
+
+    /// The cells sharing an edge with `cell`.
+    fn neighbours(&self, cell: usize) -> Vec<usize>;
+
+    /// The cells touching `cell` only at a corner.
+    fn diagonal_neighbours(&self, _cell: usize) -> Vec<usize> {
+        vec![]
+    }
+
+    /// The corners of `cell` in board coordinates, in order.
+    fn polygon(&self, cell: usize) -> Vec<Point>;
+
+    /// Width and height of the whole board in board coordinates.
+    fn extent(&self) -> (f32, f32);
+
+    /// The cell containing `point`, if any.
+    fn cell_at(&self, point: Point) -> Option<usize> {
+        (0..self.number_of_cells()).find(|&cell| contains(&self.polygon(cell), point))
+    }
+
+    /// Additional copies of cells, e.g. to show that a board wraps around, as pairs of the cell
+    /// and the polygon to draw it in.
+    #[cfg(any(feature = "gui", test))]
+    fn ghosts(&self) -> Vec<(usize, Vec<Point>)> {
+        vec![]
+    }
+}
+
+/// An axis-parallel rectangle with top left corner `(x, y)`.
+fn rectangle(x: f32, y: f32, width: f32, height: f32) -> Vec<Point> {
+    vec![
+        (x, y),
+        (x + width, y),
+        (x + width, y + height),
+        (x, y + height),
+    ]
+}
+
+/// Whether the convex polygon `polygon` contains `point`.
+fn contains(polygon: &[Point], point: Point) -> bool {
+    let (x, y) = point;
+    let mut sign = 0.0;
+
+    for (i, &(x1, y1)) in polygon.iter().enumerate() {
+        let (x2, y2) = polygon[(i + 1) % polygon.len()];
+        let cross = (x2 - x1) * (y - y1) - (y2 - y1) * (x - x1);
+        if cross == 0.0 {
+            continue;
+        }
+        if sign == 0.0 {
+            sign = cross.signum();
+        } else if cross.signum() != sign {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Which cells count as adjacent when forming connected components.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Connectivity {
+    /// Cells are adjacent if they share an edge.
+    Edges,
+
+    /// Cells are adjacent if they share an edge or a corner, e.g. up to eight neighbours for
+    /// square cells.
+    EdgesAndCorners,
+}
+
+/// The kinds of boards that can be played on.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TopologyKind {
+    Square,
+    Hexagonal,
+    Toroidal,
+    Triangular,
+}
+
+impl TopologyKind {
+    pub const NAMES: [&'static str; 4] = ["square", "hex", "torus", "triangle"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "square" => Some(TopologyKind::Square),
+            "hex" => Some(TopologyKind::Hexagonal),
+            "torus" => Some(TopologyKind::Toroidal),
+            "triangle" => Some(TopologyKind::Triangular),
+            _ => None,
+        }
+    }
+
+    pub fn create(self, rows: usize, columns: usize) -> Rc<dyn Topology> {
+        match self {
+            TopologyKind::Square => Rc::new(Square { rows, columns }),
+            TopologyKind::Hexagonal => Rc::new(Hexagonal { rows, columns }),
+            TopologyKind::Toroidal => Rc::new(Toroidal { rows, columns }),
+            TopologyKind::Triangular => Rc::new(Regions::triangular(rows, columns)),
+        }
+    }
+}
+
+/// The classic board: square cells, each touching up to four others.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Square {
+    pub rows: usize,
+    pub columns: usize,
+}
+
+impl Topology for Square {
+    fn name(&self) -> &'static str {
+        "square"
+    }
+
+    fn number_of_rows(&self) -> usize {
+        self.rows
+    }
+
+    fn number_of_columns(&self) -> usize {
+        self.columns
+    }
+
+    fn neighbours(&self, cell: usize) -> Vec<usize> {
+        let rows = self.rows;
+        let columns = self.columns;
+
+        let mut neighbours = vec![];
+        if !cell.is_multiple_of(columns) {
+            neighbours.push(cell - 1);
+        }
+        if cell % columns < columns - 1 {
+            neighbours.push(cell + 1);
+        }
+        if cell >= columns {
+            neighbours.push(cell - columns)
+        }
+        if cell < (rows - 1) * columns {
+            neighbours.push(cell + columns)
+        }
+        neighbours
+    }
+
+    fn diagonal_neighbours(&self, cell: usize) -> Vec<usize> {
+        let row = (cell / self.columns) as isize;
+        let column = (cell % self.columns) as isize;
+
+        [(-1, -1), (-1, 1), (1, -1), (1, 1)]
+            .iter()
+            .map(|(dr, dc)| (row + dr, column + dc))
+            .filter(|&(r, c)| {
+                r >= 0 && c >= 0 && r < self.rows as isize && c < self.columns as isize
+            })
+            .map(|(r, c)| r as usize * self.columns + c as usize)
+            .collect()
+    }
+
+    fn polygon(&self, cell: usize) -> Vec<Point> {
+        let x = (cell % self.columns) as f32;
+        let y = (cell / self.columns) as f32;
+        rectangle(x, y, 1.0, 1.0)
+    }
+
+    fn extent(&self) -> (f32, f32) {
+        (self.columns as f32, self.rows as f32)
+    }
+
+    fn cell_at(&self, point: Point) -> Option<usize> {
+        let (x, y) = point;
+        if x < 0.0 || y < 0.0 || x >= self.columns as f32 || y >= self.rows as f32 {
+            // Out of bounds, nothing to do
+            None
+        } else {
+            Some(y.floor() as usize * self.columns + x.floor() as usize)
+        }
+    }
+}
+
+/// Pointy-topped hexagons, each touching up to six others.  Every other row is shifted right by
+/// half a cell.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Hexagonal {
+    pub rows: usize,
+    pub columns: usize,
+}
+
+/// Distance between the centre of a hexagon and its corners, making hexagons one unit wide.
+const HEXAGON_RADIUS: f32 = 0.577_350_26;
+
+impl Hexagonal {
+    /// The centre of `cell` in board coordinates.
+    fn centre(&self, cell: usize) -> Point {
+        let row = cell / self.columns;
+        let column = cell % self.columns;
+        let shift = if row % 2 == 1 { 0.5 } else { 0.0 };
+
+        (
+            column as f32 + 0.5 + shift,
+            HEXAGON_RADIUS * (1.0 + 1.5 * row as f32),
+        )
+    }
+}
+
+impl Topology for Hexagonal {
+    fn name(&self) -> &'static str {
+        "hex"
+    }
+
+    fn number_of_rows(&self) -> usize {
+        self.rows
+    }
+
+    fn number_of_columns(&self) -> usize {
+        self.columns
+    }
+
+    fn neighbours(&self, cell: usize) -> Vec<usize> {
+        let row = (cell / self.columns) as isize;
+        let column = (cell % self.columns) as isize;
+
+        // Odd rows are shifted to the right, so their neighbours in the rows above and below are
+        // one column further right than those of even rows.
+        let shift = row % 2;
+        let offsets = [
+            (0, -1),
+            (0, 1),
+            (-1, shift - 1),
+            (-1, shift),
+            (1, shift - 1),
+            (1, shift),
+        ];
+
+        offsets
+            .iter()
+            .map(|(dr, dc)| (row + dr, column + dc))
+            .filter(|&(r, c)| {
+                r >= 0 && c >= 0 && r < self.rows as isize && c < self.columns as isize
+            })
+            .map(|(r, c)| r as usize * self.columns + c as usize)
+            .collect()
+    }
+
+    fn polygon(&self, cell: usize) -> Vec<Point> {
+        let (x, y) = self.centre(cell);
+        (0..6)
+            .map(|i| {
+                let angle = std::f32::consts::PI / 3.0 * i as f32 + std::f32::consts::PI / 6.0;
+                (
+                    x + HEXAGON_RADIUS * angle.cos(),
+                    y + HEXAGON_RADIUS * angle.sin(),
+                )
+            })
+            .collect()
+    }
+
+    fn extent(&self) -> (f32, f32) {
+        let width = self.columns as f32 + if self.rows > 1 { 0.5 } else { 0.0 };
+        let height = HEXAGON_RADIUS * (0.5 + 1.5 * self.rows as f32);
+        (width, height)
+    }
+}
+
+/// Square cells where the left and right as well as the top and bottom edges of the board are
+/// glued together.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Toroidal {
+    pub rows: usize,
+    pub columns: usize,
+}
+
+/// Width of the strips around a toroidal board showing the cells on the opposite edge.
+const GHOST_WIDTH: f32 = 0.5;
+
+impl Topology for Toroidal {
+    fn name(&self) -> &'static str {
+        "torus"
+    }
+
+    fn number_of_rows(&self) -> usize {
+        self.rows
+    }
+
+    fn number_of_columns(&self) -> usize {
+        self.columns
+    }
+
+    fn neighbours(&self, cell: usize) -> Vec<usize> {
+        let rows = self.rows;
+        let columns = self.columns;
+        let row = cell / columns;
+        let column = cell % columns;
+
+        let mut neighbours = vec![
+            row * columns + (column + columns - 1) % columns,
+            row * columns + (column + 1) % columns,
+            (row + rows - 1) % rows * columns + column,
+            (row + 1) % rows * columns + column,
+        ];
+
+        // On narrow boards, the same cell can be a neighbour on both sides.
+        neighbours.retain(|&neighbour| neighbour != cell);
+        neighbours.sort_unstable();
+        neighbours.dedup();
+        neighbours
+    }
+
+    fn diagonal_neighbours(&self, cell: usize) -> Vec<usize> {
+        let rows = self.rows;
+        let columns = self.columns;
+        let row = cell / columns;
+        let column = cell % columns;
+
+        let above = (row + rows - 1) % rows;
+        let below = (row + 1) % rows;
+        let left = (column + columns - 1) % columns;
+        let right = (column + 1) % columns;
+
+        let edge_neighbours = self.neighbours(cell);
+        let mut neighbours = vec![
+            above * columns + left,
+            above * columns + right,
+            below * columns + left,
+            below * columns + right,
+        ];
+        neighbours.retain(|neighbour| *neighbour != cell && !edge_neighbours.contains(neighbour));
+        neighbours.sort_unstable();
+        neighbours.dedup();
+        neighbours
+    }
+
+    fn polygon(&self, cell: usize) -> Vec<Point> {
+        let x = (cell % self.columns) as f32;
+        let y = (cell / self.columns) as f32;
+        rectangle(GHOST_WIDTH + x, GHOST_WIDTH + y, 1.0, 1.0)
+    }
+
+    fn extent(&self) -> (f32, f32) {
+        (
+            self.columns as f32 + 2.0 * GHOST_WIDTH,
+            self.rows as f32 + 2.0 * GHOST_WIDTH,
+        )
+    }
+
+    fn cell_at(&self, point: Point) -> Option<usize> {
+        let (width, height) = self.extent();
+        let (x, y) = point;
+        if x < 0.0 || y < 0.0 || x >= width || y >= height {
+            return None;
+        }
+
+        // Clicking a ghost means clicking the cell it is a copy of.
+        let column = ((x - GHOST_WIDTH).floor() as isize).rem_euclid(self.columns as isize);
+        let row = ((y - GHOST_WIDTH).floor() as isize).rem_euclid(self.rows as isize);
+        Some(row as usize * self.columns + column as usize)
+    }
+
+    #[cfg(any(feature = "gui", test))]
+    fn ghosts(&self) -> Vec<(usize, Vec<Point>)> {
+        let rows = self.rows;
+        let columns = self.columns;
+        let right = GHOST_WIDTH + columns as f32;
+        let bottom = GHOST_WIDTH + rows as f32;
+
+        let mut ghosts = vec![];
+        for row in 0..rows {
+            let y = GHOST_WIDTH + row as f32;
+            ghosts.push((
+                row * columns + columns - 1,
+                rectangle(0.0, y, GHOST_WIDTH, 1.0),
+            ));
+            ghosts.push((row * columns, rectangle(right, y, GHOST_WIDTH, 1.0)));
+        }
+        for column in 0..columns {
+            let x = GHOST_WIDTH + column as f32;
+            ghosts.push((
+                (rows - 1) * columns + column,
+                rectangle(x, 0.0, 1.0, GHOST_WIDTH),
+            ));
+            ghosts.push((column, rectangle(x, bottom, 1.0, GHOST_WIDTH)));
+        }
+
+        let corner = |x, y| rectangle(x, y, GHOST_WIDTH, GHOST_WIDTH);
+        ghosts.push((rows * columns - 1, corner(0.0, 0.0)));
+        ghosts.push(((rows - 1) * columns, corner(right, 0.0)));
+        ghosts.push((columns - 1, corner(0.0, bottom)));
+        ghosts.push((0, corner(right, bottom)));
+
+        ghosts
+    }
+}
+
+/// An arbitrary planar map, given by the polygon of every region and the regions it shares an
+/// edge with.  Regions touching only at a corner are derived from the polygons.
+///
+/// Like the cells of every other board, the regions are addressed by row and column, so there
+/// have to be exactly `rows * columns` of them.  Maps which do not form a grid are laid out as a
+/// single row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Regions {
+    name: &'static str,
+    rows: usize,
+    columns: usize,
+    polygons: Vec<Vec<Point>>,
+    neighbours: Vec<Vec<usize>>,
+}
+
+/// Height of a triangle with sides one unit long.
+const TRIANGLE_HEIGHT: f32 = 0.866_025_4;
+
+impl Regions {
+    /// A map of `rows` rows of `columns` regions each, with `polygons` and `neighbours` given row
+    /// by row.
+    pub fn new(
+        name: &'static str,
+        rows: usize,
+        columns: usize,
+        polygons: Vec<Vec<Point>>,
+        neighbours: Vec<Vec<usize>>,
+    ) -> Self {
+        assert_eq!(polygons.len(), rows * columns);
+        assert_eq!(neighbours.len(), polygons.len());
+        for (region, adjacent) in neighbours.iter().enumerate() {
+            for &neighbour in adjacent {
+                assert!(
+                    neighbours[neighbour].contains(&region),
+                    "Region {} is a neighbour of region {} but not the other way round",
+                    region,
+                    neighbour
+                );
+            }
+        }
+
+        Self {
+            name,
+            rows,
+            columns,
+            polygons,
+            neighbours,
+        }
+    }
+
+    /// Equilateral triangles pointing up and down in turn, each touching up to three others.  The
+    /// first triangle of every even row points up, that of every odd row points down.
+    pub fn triangular(rows: usize, columns: usize) -> Self {
+        let mut polygons = vec![];
+        let mut neighbours = vec![];
+
+        for row in 0..rows {
+            for column in 0..columns {
+                let x = column as f32 / 2.0;
+                let top = row as f32 * TRIANGLE_HEIGHT;
+                let bottom = top + TRIANGLE_HEIGHT;
+                let points_up = (row + column) % 2 == 0;
+
+                polygons.push(if points_up {
+                    vec![(x + 0.5, top), (x + 1.0, bottom), (x, bottom)]
+                } else {
+                    vec![(x, top), (x + 1.0, top), (x + 0.5, bottom)]
+                });
+
+                let cell = row * columns + column;
+                let mut adjacent = vec![];
+                if column > 0 {
+                    adjacent.push(cell - 1);
+                }
+                if column + 1 < columns {
+                    adjacent.push(cell + 1);
+                }
+                // Triangles pointing up share their base with the one below, triangles pointing
+                // down with the one above.
+                if points_up && row + 1 < rows {
+                    adjacent.push(cell + columns);
+                } else if !points_up && row > 0 {
+                    adjacent.push(cell - columns);
+                }
+                neighbours.push(adjacent);
+            }
+        }
+
+        Self::new("triangle", rows, columns, polygons, neighbours)
+    }
+}
+
+impl Topology for Regions {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn number_of_rows(&self) -> usize {
+        self.rows
+    }
+
+    fn number_of_columns(&self) -> usize {
+        self.columns
+    }
+
+    fn neighbours(&self, cell: usize) -> Vec<usize> {
+        self.neighbours[cell].clone()
+    }
+
+    /// Regions sharing a corner but not an edge with `cell`.  These are only needed when corners
+    /// count, so they are found on demand rather than for every map.
+    fn diagonal_neighbours(&self, cell: usize) -> Vec<usize> {
+        let polygon = &self.polygons[cell];
+        let shares_corner = |other: &[Point]| {
+            polygon.iter().any(|&(x1, y1)| {
+                other
+                    .iter()
+                    .any(|&(x2, y2)| (x1 - x2).abs() < 1e-4 && (y1 - y2).abs() < 1e-4)
+            })
+        };
+
+        (0..self.polygons.len())
+            .filter(|&other| {
+                other != cell
+                    && !self.neighbours[cell].contains(&other)
+                    && shares_corner(&self.polygons[other])
+            })
+            .collect()
+    }
+
+    fn polygon(&self, cell: usize) -> Vec<Point> {
+        self.polygons[cell].clone()
+    }
+
+    fn extent(&self) -> (f32, f32) {
+        self.polygons
+            .iter()
+            .flatten()
+            .fold((0.0, 0.0), |(width, height), &(x, y)| {
+                (width.max(x), height.max(y))
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_symmetric(topology: &dyn Topology) {
+        for cell in 0..topology.number_of_cells() {
+            for neighbour in topology.neighbours(cell) {
+                assert!(topology.neighbours(neighbour).contains(&cell));
+            }
+        }
+    }
+
+    #[test]
+    fn should_have_four_neighbours_inside_square_grid() {
+        let square = Square {
+            rows: 3,
+            columns: 3,
+        };
+
+        assert_eq!(square.neighbours(4).len(), 4);
+        assert_eq!(square.neighbours(0).len(), 2);
+        assert_symmetric(&square);
+    }
+
+    #[test]
+    fn should_have_four_diagonal_neighbours_inside_square_grid() {
+        let square = Square {
+            rows: 3,
+            columns: 3,
+        };
+
+        assert_eq!(square.diagonal_neighbours(4), vec![0, 2, 6, 8]);
+        assert_eq!(square.diagonal_neighbours(0), vec![4]);
+    }
+
+    #[test]
+    fn should_have_six_neighbours_inside_hexagonal_grid() {
+        let hexagonal = Hexagonal {
+            rows: 4,
+            columns: 4,
+        };
+
+        // Row 1 is shifted to the right, so cell 5 touches cells 1 and 2 above and 9 and 10
+        // below.
+        let mut neighbours = hexagonal.neighbours(5);
+        neighbours.sort_unstable();
+        assert_eq!(neighbours, vec![1, 2, 4, 6, 9, 10]);
+
+        let mut neighbours = hexagonal.neighbours(10);
+        neighbours.sort_unstable();
+        assert_eq!(neighbours, vec![5, 6, 9, 11, 13, 14]);
+
+        assert_symmetric(&hexagonal);
+    }
+
+    #[test]
+    fn should_find_cell_at_its_centre() {
+        let hexagonal = Hexagonal {
+            rows: 5,
+            columns: 7,
+        };
+
+        for cell in 0..hexagonal.number_of_cells() {
+            assert_eq!(hexagonal.cell_at(hexagonal.centre(cell)), Some(cell));
+        }
+        assert_eq!(hexagonal.cell_at((-1.0, 0.5)), None);
+    }
+
+    #[test]
+    fn should_wrap_around_the_edges_of_a_torus() {
+        let toroidal = Toroidal {
+            rows: 3,
+            columns: 4,
+        };
+
+        assert_eq!(toroidal.neighbours(0), vec![1, 3, 4, 8]);
+        assert_eq!(toroidal.diagonal_neighbours(0), vec![5, 7, 9, 11]);
+        assert_symmetric(&toroidal);
+    }
+
+    #[test]
+    fn should_not_repeat_neighbours_on_a_small_torus() {
+        let toroidal = Toroidal {
+            rows: 2,
+            columns: 2,
+        };
+
+        assert_eq!(toroidal.neighbours(0), vec![1, 2]);
+    }
+
+    #[test]
+    fn should_map_ghosts_to_cells_on_the_opposite_edge() {
+        let toroidal = Toroidal {
+            rows: 3,
+            columns: 4,
+        };
+
+        assert_eq!(toroidal.cell_at((0.25, 0.75)), Some(3));
+        assert_eq!(toroidal.cell_at((4.75, 3.75)), Some(0));
+        for (cell, polygon) in toroidal.ghosts() {
+            let (x, y) = polygon[0];
+            assert_eq!(toroidal.cell_at((x + 0.1, y + 0.1)), Some(cell));
+        }
+    }
+
+    #[test]
+    fn should_find_square_cell_containing_point() {
+        let square = Square {
+            rows: 2,
+            columns: 3,
+        };
+
+        assert_eq!(square.cell_at((2.5, 1.5)), Some(5));
+        assert_eq!(square.cell_at((3.0, 1.5)), None);
+    }
+
+    #[test]
+    fn should_have_three_neighbours_inside_triangular_grid() {
+        let triangular = Regions::triangular(3, 5);
+
+        // Cell 7 points down and shares its top edge with cell 2.
+        assert_eq!(triangular.neighbours(7), vec![6, 8, 2]);
+        // Cell 6 points up and shares its base with cell 11.
+        assert_eq!(triangular.neighbours(6), vec![5, 7, 11]);
+        assert_eq!(triangular.diagonal_neighbours(7).len(), 9);
+        assert_symmetric(&triangular);
+    }
+
+    #[test]
+    fn should_find_triangle_containing_point() {
+        let triangular = Regions::triangular(2, 4);
+
+        assert_eq!(triangular.extent(), (2.5, 2.0 * TRIANGLE_HEIGHT));
+        assert_eq!(triangular.cell_at((0.5, 0.5)), Some(0));
+        assert_eq!(triangular.cell_at((1.0, 0.25)), Some(1));
+        assert_eq!(triangular.cell_at((0.1, 0.1)), None);
+    }
+
+    #[test]
+    fn should_build_map_from_adjacency_list() {
+        let regions = Regions::new(
+            "strip",
+            1,
+            2,
+            vec![rectangle(0.0, 0.0, 1.0, 1.0), rectangle(1.0, 0.0, 2.0, 1.0)],
+            vec![vec![1], vec![0]],
+        );
+
+        assert_eq!(regions.number_of_cells(), 2);
+        assert_eq!(regions.extent(), (3.0, 1.0));
+        assert_eq!(regions.cell_at((2.5, 0.5)), Some(1));
+    }
+}
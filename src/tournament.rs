@@ -0,0 +1,259 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::Serialize;
+
+use crate::game::{allowed_clicks, Game};
+use crate::grid::Grid;
+use crate::strategy::Strategy;
+use crate::topology::{Connectivity, TopologyKind};
+
+const SIZES: [u32; 4] = [6, 10, 14, 18];
+const NUMBERS_OF_COLOURS: [u32; 4] = [3, 4, 6, 8];
+
+/// One of the boards every strategy plays.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Board {
+    pub size: u32,
+    pub number_of_colours: u32,
+    pub seed: u64,
+}
+
+/// The same boards every time: `boards_per_kind` boards for every combination of size and
+/// number of colours.
+pub fn boards(boards_per_kind: u64) -> Vec<Board> {
+    let mut boards = vec![];
+    for size in SIZES {
+        for number_of_colours in NUMBERS_OF_COLOURS {
+            for seed in 0..boards_per_kind {
+                boards.push(Board {
+                    size,
+                    number_of_colours,
+                    seed,
+                });
+            }
+        }
+    }
+    boards
+}
+
+/// How one strategy did on one board.
+#[derive(Clone, Debug, Serialize)]
+pub struct Record {
+    pub strategy: String,
+    pub size: u32,
+    pub colours: u32,
+    pub seed: u64,
+    pub moves: u32,
+    pub allowed_moves: u32,
+
+    /// Whether the board was flooded within the move budget.
+    pub solved: bool,
+
+    /// Thinking time in seconds.
+    pub time: f64,
+}
+
+/// Let `strategy` flood `board`.  A strategy that keeps going long after the budget is used up
+/// is stopped, as it may never finish.
+pub fn play(strategy: &mut dyn Strategy, board: Board) -> Record {
+    let grid = Grid::generate_from_seed(board.size as usize, board.number_of_colours, board.seed);
+    let allowed = allowed_clicks(
+        board.size,
+        board.number_of_colours,
+        TopologyKind::Square,
+        Connectivity::Edges,
+    );
+    let mut game = Game::from_grid(&grid, allowed);
+    let mut rng = StdRng::seed_from_u64(board.seed);
+    let maximum_moves = grid.cells.len() as u32 * board.number_of_colours;
+
+    let start = Instant::now();
+    while game.graph.components.len() > 1 && game.number_of_clicks < maximum_moves {
+        let colour = strategy.choose(&game, &mut rng);
+        game.fill_component_of_top_left_cell_with(colour);
+    }
+
+    Record {
+        strategy: strategy.name().to_string(),
+        size: board.size,
+        colours: board.number_of_colours,
+        seed: board.seed,
+        moves: game.number_of_clicks,
+        allowed_moves: allowed,
+        solved: game.graph.components.len() == 1 && game.number_of_clicks <= allowed,
+        time: start.elapsed().as_secs_f64(),
+    }
+}
+
+/// Let every strategy play every board.
+pub fn run(strategies: &mut [Box<dyn Strategy>], boards: &[Board]) -> Vec<Record> {
+    strategies
+        .iter_mut()
+        .flat_map(|strategy| {
+            boards
+                .iter()
+                .map(|&board| play(&mut **strategy, board))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// The overall result of one strategy.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Standing {
+    pub strategy: String,
+    pub boards: usize,
+    pub failures: usize,
+    pub average_moves: f64,
+
+    /// The average number of moves as a share of the budget.
+    pub average_share: f64,
+
+    /// Thinking time in seconds for all boards together.
+    pub time: f64,
+}
+
+/// The standings of all strategies, best first: fewest failures, then fewest moves.
+pub fn leaderboard(records: &[Record]) -> Vec<Standing> {
+    let mut standings: Vec<Standing> = vec![];
+
+    for record in records {
+        let index = match standings.iter().position(|s| s.strategy == record.strategy) {
+            Some(index) => index,
+            None => {
+                standings.push(Standing {
+                    strategy: record.strategy.clone(),
+                    boards: 0,
+                    failures: 0,
+                    average_moves: 0.0,
+                    average_share: 0.0,
+                    time: 0.0,
+                });
+                standings.len() - 1
+            }
+        };
+
+        // Sums for now, averaged below.
+        let standing = &mut standings[index];
+        standing.boards += 1;
+        standing.failures += usize::from(!record.solved);
+        standing.average_moves += record.moves as f64;
+        standing.average_share += record.moves as f64 / record.allowed_moves.max(1) as f64;
+        standing.time += record.time;
+    }
+
+    for standing in &mut standings {
+        standing.average_moves /= standing.boards as f64;
+        standing.average_share /= standing.boards as f64;
+    }
+
+    standings.sort_by(|a, b| {
+        a.failures
+            .cmp(&b.failures)
+            .then(a.average_moves.total_cmp(&b.average_moves))
+    });
+    standings
+}
+
+/// The leaderboard as a table for the terminal.
+pub fn format_leaderboard(standings: &[Standing]) -> String {
+    let mut table = format!(
+        "{:<4} {:<12} {:>7} {:>9} {:>10} {:>8} {:>10}\n",
+        "Rank", "Strategy", "Boards", "Failures", "Avg moves", "Budget", "Time (s)"
+    );
+    for (rank, standing) in standings.iter().enumerate() {
+        let _ = writeln!(
+            table,
+            "{:<4} {:<12} {:>7} {:>9} {:>10.2} {:>7.0}% {:>10.3}",
+            rank + 1,
+            standing.strategy,
+            standing.boards,
+            standing.failures,
+            standing.average_moves,
+            100.0 * standing.average_share,
+            standing.time
+        );
+    }
+    table
+}
+
+pub fn to_csv(records: &[Record]) -> String {
+    let mut csv = "strategy,size,colours,seed,moves,allowed_moves,solved,time\n".to_string();
+    for record in records {
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{},{},{},{}",
+            record.strategy,
+            record.size,
+            record.colours,
+            record.seed,
+            record.moves,
+            record.allowed_moves,
+            record.solved,
+            record.time
+        );
+    }
+    csv
+}
+
+/// Write the records to `path`, as JSON if it ends in `.json` and as CSV otherwise.
+pub fn save(records: &[Record], path: &Path) -> Result<(), String> {
+    let contents = if path
+        .extension()
+        .is_some_and(|extension| extension == "json")
+    {
+        serde_json::to_string_pretty(records).map_err(|error| error.to_string())?
+    } else {
+        to_csv(records)
+    };
+
+    fs::write(path, contents).map_err(|error| format!("Cannot write {}: {}", path.display(), error))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::strategy;
+
+    fn records() -> Vec<Record> {
+        let boards: Vec<Board> = boards(1)
+            .into_iter()
+            .filter(|board| board.size <= 10)
+            .collect();
+
+        run(&mut strategy::all(), &boards)
+    }
+
+    #[test]
+    fn should_let_every_strategy_flood_every_board() {
+        let records = records();
+
+        assert_eq!(records.len(), 2 * 8);
+        for record in &records {
+            assert!(record.moves < record.size * record.size * record.colours);
+        }
+    }
+
+    #[test]
+    fn should_rank_greedy_above_random() {
+        let standings = leaderboard(&records());
+
+        assert_eq!(standings[0].strategy, "greedy");
+        assert_eq!(standings[0].boards, 8);
+        assert!(standings[0].average_moves < standings[1].average_moves);
+    }
+
+    #[test]
+    fn should_write_one_line_per_record() {
+        let records = records();
+        let csv = to_csv(&records);
+
+        assert_eq!(csv.lines().count(), records.len() + 1);
+        assert!(csv.starts_with("strategy,size,colours,seed,moves,allowed_moves,solved,time\n"));
+    }
+}
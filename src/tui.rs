@@ -0,0 +1,462 @@
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use crossterm::event::{
+    self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+    KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use crossterm::style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor};
+use crossterm::{cursor, queue, terminal};
+
+use crate::colour::{self, Colour, Rgb};
+use crate::config::{Key, Keybindings};
+use crate::game::{Game, GameState, Settings};
+use crate::graph::Lock;
+use crate::mode::{Continuation, Mode};
+use crate::statistics::{self, Statistics};
+
+/// The keys choosing the colours in play, in order.
+const COLOUR_KEYS: [char; 16] = [
+    '1', '2', '3', '4', '5', '6', '7', '8', '9', '0', 'a', 'b', 'c', 'd', 'e', 'f',
+];
+
+/// The line with the keys of the colours, below the status line.
+const LEGEND_ROW: u16 = 1;
+
+/// The line telling the player how they did once the game is over, between the keys and the
+/// board.
+const VERDICT_ROW: u16 = 2;
+
+/// The first line of the board.
+const BOARD_ROW: u16 = 3;
+
+/// How long to wait for input before redrawing, e.g. to update the clock.
+const FRAME_DURATION: Duration = Duration::from_millis(100);
+
+/// Which cell every character of the board area shows, for a given board and terminal size.
+/// Board coordinates are stretched to make up for characters being about twice as high as wide.
+struct Layout {
+    width: u16,
+    height: u16,
+    cells: Vec<Option<usize>>,
+
+    /// The character in the middle of every cell, if the cell is large enough to have one.
+    centres: Vec<Option<(u16, u16)>>,
+}
+
+impl Layout {
+    fn new(game: &Game, (columns, rows): (u16, u16)) -> Self {
+        let (board_width, board_height) = game.topology.extent();
+        let available_rows = rows.saturating_sub(BOARD_ROW).max(1) as f32;
+        let fit = (columns as f32 / (2.0 * board_width)).min(available_rows / board_height);
+        // Whole characters per unit keep square cells the same size, as long as there is room.
+        let scale = if fit >= 1.0 { fit.floor() } else { fit };
+
+        let width = (2.0 * scale * board_width).ceil() as u16;
+        let height = (scale * board_height).ceil() as u16;
+        let to_board =
+            |x: u16, y: u16| ((x as f32 + 0.5) / (2.0 * scale), (y as f32 + 0.5) / scale);
+
+        let is_void = |cell: usize| game.mask.as_ref().is_some_and(|mask| mask.is_void(cell));
+        let mut cells = Vec::with_capacity(width as usize * height as usize);
+        for y in 0..height {
+            for x in 0..width {
+                cells.push(
+                    game.topology
+                        .cell_at(to_board(x, y))
+                        .filter(|&cell| !is_void(cell)),
+                );
+            }
+        }
+
+        let centres = (0..game.topology.number_of_cells())
+            .map(|cell| {
+                let polygon = game.topology.polygon(cell);
+                let (sum_x, sum_y) = polygon
+                    .iter()
+                    .fold((0.0, 0.0), |(sum_x, sum_y), &(x, y)| (sum_x + x, sum_y + y));
+                let n = polygon.len() as f32;
+                let x = (2.0 * scale * sum_x / n) as u16;
+                let y = (scale * sum_y / n) as u16;
+                (x < width && y < height && cells[(y * width + x) as usize] == Some(cell))
+                    .then_some((x, y))
+            })
+            .collect();
+
+        Self {
+            width,
+            height,
+            cells,
+            centres,
+        }
+    }
+
+    fn cell_at(&self, x: u16, y: u16) -> Option<usize> {
+        let y = y.checked_sub(BOARD_ROW)?;
+        (x < self.width && y < self.height)
+            .then(|| self.cells[(y * self.width + x) as usize])
+            .flatten()
+    }
+}
+
+/// The key of the configuration matching `code`, if there is one.
+fn bound_key(code: KeyCode) -> Option<Key> {
+    match code {
+        KeyCode::Char(' ') => Some(Key::Space),
+        KeyCode::Char(character) if character.is_ascii_alphanumeric() => {
+            Some(Key::Character(character.to_ascii_lowercase()))
+        }
+        KeyCode::Tab => Some(Key::Tab),
+        KeyCode::Enter => Some(Key::Enter),
+        KeyCode::Esc => Some(Key::Escape),
+        KeyCode::Backspace => Some(Key::Backspace),
+        KeyCode::F(number) => Some(Key::Function(number)),
+        _ => None,
+    }
+}
+
+fn terminal_colour((r, g, b): (u8, u8, u8)) -> Color {
+    Color::Rgb { r, g, b }
+}
+
+/// Black or white, whichever stands out more in front of `rgb`.
+fn contrasting_colour(rgb: Rgb) -> Color {
+    if colour::is_light(rgb) {
+        Color::Black
+    } else {
+        Color::White
+    }
+}
+
+/// Show the statistics as a table with aligned columns.
+fn render_statistics(statistics: &Statistics, stdout: &mut impl Write) -> io::Result<()> {
+    let rows = statistics.table();
+    let widths: Vec<usize> = (0..statistics::COLUMNS.len())
+        .map(|column| rows.iter().map(|row| row[column].len()).max().unwrap_or(0))
+        .collect();
+
+    queue!(
+        stdout,
+        ResetColor,
+        terminal::Clear(terminal::ClearType::All),
+        cursor::MoveTo(0, 0),
+        Print("Statistics (tab to go back)")
+    )?;
+
+    if rows.len() == 1 {
+        queue!(stdout, cursor::MoveTo(0, 2), Print("No games played yet."))?;
+        return stdout.flush();
+    }
+
+    for (line, row) in rows.iter().enumerate() {
+        let text: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(text, &width)| format!("{:<width$}", text, width = width))
+            .collect();
+        queue!(
+            stdout,
+            cursor::MoveTo(0, 2 + line as u16),
+            Print(text.join("  "))
+        )?;
+    }
+    queue!(
+        stdout,
+        cursor::MoveTo(0, 3 + rows.len() as u16),
+        Print(statistics::LEGEND)
+    )?;
+
+    stdout.flush()
+}
+
+/// Puts the terminal into the state needed for the game and restores it when dropped, even if the
+/// game panics.
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn new() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        let mut stdout = io::stdout();
+        crossterm::execute!(
+            stdout,
+            terminal::EnterAlternateScreen,
+            EnableMouseCapture,
+            cursor::Hide
+        )?;
+        Ok(Self)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = crossterm::execute!(
+            io::stdout(),
+            cursor::Show,
+            DisableMouseCapture,
+            terminal::LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Flood-It in a terminal, played with the keyboard or the mouse.
+pub struct Tui {
+    /// What kind of boards to generate.
+    settings: Settings,
+
+    /// How the game is played, e.g. against the clock.
+    mode: Mode,
+
+    /// Whether to show the key of every colour in its cells.
+    show_symbols: bool,
+
+    keys: Keybindings,
+
+    layout: Layout,
+
+    /// The cell to flood with when pressing enter.
+    cursor: usize,
+
+    /// The statistics shown instead of the board, as read when they were opened.
+    statistics: Option<Statistics>,
+}
+
+impl Tui {
+    pub fn create(
+        settings: Settings,
+        mut mode: Mode,
+        show_symbols: bool,
+        keys: Keybindings,
+        game: &Game,
+    ) -> Self {
+        mode.start();
+
+        Self {
+            settings,
+            mode,
+            show_symbols,
+            keys,
+            layout: Layout::new(game, terminal::size().unwrap_or((80, 24))),
+            cursor: 0,
+            statistics: None,
+        }
+    }
+
+    /// Play until the player quits.
+    pub fn run(mut self, game: &mut Game) -> io::Result<()> {
+        let _guard = TerminalGuard::new()?;
+        let start = Instant::now();
+        let mut stdout = io::stdout();
+
+        loop {
+            self.mode.update(game, start.elapsed().as_secs_f64());
+            self.render(game, &mut stdout)?;
+
+            if !event::poll(FRAME_DURATION)? {
+                continue;
+            }
+            match event::read()? {
+                Event::Key(key) if key.kind != KeyEventKind::Release => {
+                    let keep_playing = self.handle_key(game, key);
+                    if !keep_playing {
+                        self.mode.abandon(game);
+                        return Ok(());
+                    }
+                }
+                Event::Mouse(mouse) => self.handle_mouse(game, mouse),
+                Event::Resize(columns, rows) => self.layout = Layout::new(game, (columns, rows)),
+                _ => (),
+            }
+        }
+    }
+
+    fn render(&self, game: &Game, stdout: &mut impl Write) -> io::Result<()> {
+        if let Some(statistics) = &self.statistics {
+            return render_statistics(statistics, stdout);
+        }
+
+        let grid = game.grid();
+        let palette = &self.settings.palette;
+
+        queue!(
+            stdout,
+            ResetColor,
+            terminal::Clear(terminal::ClearType::All),
+            cursor::MoveTo(0, 0),
+            Print(self.mode.status(game))
+        )?;
+        let note = match game.state {
+            GameState::Solving if self.mode.is_paused() => "   Paused, press p to go on",
+            GameState::Solving => "",
+            GameState::Solved => "   Press n for a new board",
+            GameState::TimeUp => "   Press n to try again",
+        };
+        queue!(stdout, Print(note))?;
+
+        if let Some(verdict) = &game.verdict {
+            queue!(stdout, cursor::MoveTo(0, VERDICT_ROW), Print(verdict))?;
+        }
+
+        queue!(stdout, cursor::MoveTo(0, LEGEND_ROW))?;
+        for (&colour, key) in game.colours.iter().zip(COLOUR_KEYS) {
+            queue!(
+                stdout,
+                ResetColor,
+                Print(format!("{} ", key)),
+                SetBackgroundColor(terminal_colour(palette.rgb(colour))),
+                Print("  "),
+                ResetColor,
+                Print("  ")
+            )?;
+        }
+
+        if self.mode.hides_board() {
+            return stdout.flush();
+        }
+
+        for y in 0..self.layout.height {
+            queue!(stdout, cursor::MoveTo(0, BOARD_ROW + y))?;
+            for x in 0..self.layout.width {
+                let Some(cell) = self.layout.cells[(y * self.layout.width + x) as usize] else {
+                    queue!(stdout, ResetColor, Print(' '))?;
+                    continue;
+                };
+
+                let rgb = palette.rgb(grid.cells[cell]);
+                let label = if self.layout.centres[cell] != Some((x, y)) {
+                    ' '
+                } else if cell == self.cursor && game.state == GameState::Solving {
+                    '@'
+                } else if let Some(lock) = grid.locks.get(&cell) {
+                    let (Lock::Moves(count) | Lock::Matches(count)) = *lock;
+                    char::from_digit(count.min(9), 10).unwrap()
+                } else if self.show_symbols {
+                    self.key_of(game, grid.cells[cell]).unwrap_or(' ')
+                } else {
+                    ' '
+                };
+
+                queue!(
+                    stdout,
+                    SetBackgroundColor(terminal_colour(rgb)),
+                    SetForegroundColor(contrasting_colour(rgb)),
+                    Print(label)
+                )?;
+            }
+        }
+
+        queue!(stdout, ResetColor)?;
+        stdout.flush()
+    }
+
+    fn key_of(&self, game: &Game, colour: Colour) -> Option<char> {
+        let index = game.colours.iter().position(|&other| other == colour)?;
+        COLOUR_KEYS.get(index).copied()
+    }
+
+    /// React to a key press.  Returns whether to keep playing.
+    fn handle_key(&mut self, game: &mut Game, key: KeyEvent) -> bool {
+        let columns = game.topology.number_of_columns();
+        let cells = game.topology.number_of_cells();
+
+        let bound = bound_key(key.code);
+        let is_bound = |keys: &[Key]| bound.is_some_and(|bound| keys.contains(&bound));
+
+        match key.code {
+            KeyCode::Esc => return false,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return false,
+            _ if is_bound(&self.keys.quit) => return false,
+            _ if is_bound(&self.keys.statistics) => {
+                self.statistics = match self.statistics {
+                    Some(_) => None,
+                    None => Some(Statistics::load()),
+                }
+            }
+            _ if is_bound(&self.keys.pause) && game.state == GameState::Solving => {
+                self.mode.toggle_pause()
+            }
+            _ if is_bound(&self.keys.new_board) && game.state != GameState::Solving => {
+                self.regenerate(game)
+            }
+            KeyCode::Left if !self.cursor.is_multiple_of(columns) => self.cursor -= 1,
+            KeyCode::Right if self.cursor % columns + 1 < columns => self.cursor += 1,
+            KeyCode::Up if self.cursor >= columns => self.cursor -= columns,
+            KeyCode::Down if self.cursor + columns < cells => self.cursor += columns,
+            KeyCode::Enter => {
+                let is_void = game.mask.as_ref().is_some_and(|m| m.is_void(self.cursor));
+                if !is_void {
+                    let position = game.grid().position(self.cursor);
+                    let colour = game.graph.find_component(&position).colour;
+                    self.play(game, colour);
+                }
+            }
+            KeyCode::Char(character) => {
+                if let Some(index) = COLOUR_KEYS.iter().position(|&key| key == character) {
+                    if let Some(&colour) = game.colours.get(index) {
+                        self.play(game, colour);
+                    }
+                }
+            }
+            _ => (),
+        }
+
+        true
+    }
+
+    fn handle_mouse(&mut self, game: &mut Game, mouse: MouseEvent) {
+        if mouse.kind != MouseEventKind::Down(MouseButton::Left) {
+            return;
+        }
+        if self.statistics.is_some() {
+            self.statistics = None;
+            return;
+        }
+        if game.state != GameState::Solving {
+            self.regenerate(game);
+            return;
+        }
+
+        // Every swatch of the legend takes up five characters, including the key and the space.
+        let colour = if mouse.row == LEGEND_ROW {
+            game.colours.get(mouse.column as usize / 5).copied()
+        } else {
+            self.layout.cell_at(mouse.column, mouse.row).map(|cell| {
+                game.graph
+                    .find_component(&game.grid().position(cell))
+                    .colour
+            })
+        };
+
+        if let Some(colour) = colour {
+            self.play(game, colour);
+        }
+    }
+
+    fn play(&mut self, game: &mut Game, colour: Colour) {
+        if game.state != GameState::Solving || self.mode.is_paused() {
+            return;
+        }
+
+        game.fill_component_of_top_left_cell_with(colour);
+
+        if game.graph.components.len() == 1 {
+            match self.mode.board_solved(game) {
+                Continuation::Wait => (),
+                Continuation::NextBoard => self.next_board(game),
+            }
+        }
+    }
+
+    /// Start a new round.
+    fn regenerate(&mut self, game: &mut Game) {
+        self.mode.start();
+        self.next_board(game);
+    }
+
+    /// Replace the board without starting a new round.
+    fn next_board(&mut self, game: &mut Game) {
+        *game = self.mode.create_game(&self.settings);
+        self.layout = Layout::new(game, terminal::size().unwrap_or((80, 24)));
+        self.cursor = 0;
+    }
+}
@@ -1,163 +1,787 @@
+use std::f32::consts::PI;
+use std::rc::Rc;
+
 use macroquad::prelude::*;
 
-use crate::colour::Colour;
-use crate::game::{Game, GameState};
-use crate::graph::Position;
-
-fn macroquad_colour(colour: Colour) -> Color {
-    match colour {
-        Colour::Red => color_u8!(255, 0, 0, 255),
-        Colour::Yellow => color_u8!(255, 255, 0, 255),
-        Colour::Green => color_u8!(0, 176, 0, 255),
-        Colour::LightBrown => color_u8!(255, 204, 102, 255),
-        Colour::Purple => color_u8!(128, 0, 128, 255),
-        Colour::Cyan => color_u8!(0, 255, 255, 255),
-        Colour::Blue => color_u8!(0, 0, 255, 255),
-        Colour::Fuchsia => color_u8!(255, 0, 255, 255),
+use crate::animation::Animation;
+use crate::colour::{self, Colour, Palette, Rgb};
+use crate::config::Key;
+use crate::game::{Game, GameState, Settings};
+use crate::graph::{Lock, Position};
+use crate::grid::Grid;
+use crate::mode::{Continuation, Mode};
+use crate::statistics::{self, Statistics};
+use crate::theme::{Appearance, Theme};
+use crate::topology::Point;
+
+/// Height of the status line above the grid in pixels.
+const STATUS_LINE_HEIGHT: f32 = 32.0;
+
+const FONT_SIZE: f32 = 24.0;
+
+/// How close two corners of cells have to be to count as the same point, in board coordinates.
+const CORNER_TOLERANCE: f32 = 1e-4;
+
+/// How many line segments make up a rounded corner.
+const SEGMENTS_PER_CORNER: usize = 4;
+
+fn key_code(key: Key) -> Option<KeyCode> {
+    const LETTERS: [KeyCode; 26] = [
+        KeyCode::A,
+        KeyCode::B,
+        KeyCode::C,
+        KeyCode::D,
+        KeyCode::E,
+        KeyCode::F,
+        KeyCode::G,
+        KeyCode::H,
+        KeyCode::I,
+        KeyCode::J,
+        KeyCode::K,
+        KeyCode::L,
+        KeyCode::M,
+        KeyCode::N,
+        KeyCode::O,
+        KeyCode::P,
+        KeyCode::Q,
+        KeyCode::R,
+        KeyCode::S,
+        KeyCode::T,
+        KeyCode::U,
+        KeyCode::V,
+        KeyCode::W,
+        KeyCode::X,
+        KeyCode::Y,
+        KeyCode::Z,
+    ];
+    const DIGITS: [KeyCode; 10] = [
+        KeyCode::Key0,
+        KeyCode::Key1,
+        KeyCode::Key2,
+        KeyCode::Key3,
+        KeyCode::Key4,
+        KeyCode::Key5,
+        KeyCode::Key6,
+        KeyCode::Key7,
+        KeyCode::Key8,
+        KeyCode::Key9,
+    ];
+    const FUNCTION_KEYS: [KeyCode; 12] = [
+        KeyCode::F1,
+        KeyCode::F2,
+        KeyCode::F3,
+        KeyCode::F4,
+        KeyCode::F5,
+        KeyCode::F6,
+        KeyCode::F7,
+        KeyCode::F8,
+        KeyCode::F9,
+        KeyCode::F10,
+        KeyCode::F11,
+        KeyCode::F12,
+    ];
+
+    match key {
+        Key::Character(character @ 'a'..='z') => Some(LETTERS[character as usize - 'a' as usize]),
+        Key::Character(character @ '0'..='9') => Some(DIGITS[character as usize - '0' as usize]),
+        Key::Character(_) => None,
+        Key::Space => Some(KeyCode::Space),
+        Key::Tab => Some(KeyCode::Tab),
+        Key::Enter => Some(KeyCode::Enter),
+        Key::Escape => Some(KeyCode::Escape),
+        Key::Backspace => Some(KeyCode::Backspace),
+        Key::Function(number) => (number as usize)
+            .checked_sub(1)
+            .and_then(|index| FUNCTION_KEYS.get(index).copied()),
     }
 }
 
-fn draw_cell(grid_x: f32, grid_y: f32, cell_size: f32, position: &Position, colour: Colour) {
-    let color = macroquad_colour(colour);
-    draw_rectangle(
-        grid_x + cell_size * position.column as f32,
-        grid_y + cell_size * position.row as f32,
-        cell_size,
-        cell_size,
-        color,
-    );
+/// Whether any of `keys` has been pressed since the last frame.
+pub fn is_pressed(keys: &[Key]) -> bool {
+    keys.iter()
+        .filter_map(|&key| key_code(key))
+        .any(is_key_pressed)
 }
 
-pub struct Ui {
-    /// Size of the grid in cells.
-    size: u32,
+fn rgb_colour((r, g, b): (u8, u8, u8)) -> Color {
+    color_u8!(r, g, b, 255)
+}
+
+fn macroquad_colour(palette: &Palette, colour: Colour) -> Color {
+    rgb_colour(palette.rgb(colour))
+}
+
+/// Black or white, whichever stands out more in front of `rgb`.
+fn contrasting_colour(rgb: Rgb) -> Color {
+    if colour::is_light(rgb) {
+        BLACK
+    } else {
+        WHITE
+    }
+}
+
+/// Fill the convex polygon with the given corners in screen coordinates.
+fn draw_cell(corners: &[Vec2], color: Color) {
+    for i in 1..corners.len() - 1 {
+        draw_triangle(corners[0], corners[i], corners[i + 1], color);
+    }
+}
+
+fn centre(corners: &[Vec2]) -> Vec2 {
+    corners.iter().fold(Vec2::ZERO, |sum, &corner| sum + corner) / corners.len() as f32
+}
+
+/// Shrink the cell with the given corners by `gap` and round off its corners with a radius of
+/// `corner_radius`, both as shares of the length of its shortest edge.
+fn shape_cell(corners: &[Vec2], gap: f32, corner_radius: f32) -> Vec<Vec2> {
+    let centre = centre(corners);
+    let corners: Vec<Vec2> = corners
+        .iter()
+        .map(|&corner| centre + (1.0 - gap) * (corner - centre))
+        .collect();
+    if corner_radius <= 0.0 {
+        return corners;
+    }
+
+    let n = corners.len();
+    let shortest_edge = (0..n)
+        .map(|i| corners[i].distance(corners[(i + 1) % n]))
+        .fold(f32::MAX, f32::min);
+    let radius = corner_radius * shortest_edge;
 
-    /// The number of different colours that can be used for the cells.
-    number_of_colours: u32,
+    let mut rounded = Vec::with_capacity(n * (SEGMENTS_PER_CORNER + 1));
+    for i in 0..n {
+        let corner = corners[i];
+        let previous = corner + radius * (corners[(i + n - 1) % n] - corner).normalize();
+        let next = corner + radius * (corners[(i + 1) % n] - corner).normalize();
 
-    /// Size of the grid in pixels.  Assumes the grid is always square.
-    grid_size: f32,
+        // A quadratic Bézier curve from one edge to the next, pulled towards the corner.
+        for step in 0..=SEGMENTS_PER_CORNER {
+            let t = step as f32 / SEGMENTS_PER_CORNER as f32;
+            rounded.push(
+                (1.0 - t) * (1.0 - t) * previous + 2.0 * (1.0 - t) * t * corner + t * t * next,
+            );
+        }
+    }
+    rounded
+}
+
+fn same_point(a: Point, b: Point) -> bool {
+    (a.0 - b.0).abs() < CORNER_TOLERANCE && (a.1 - b.1).abs() < CORNER_TOLERANCE
+}
+
+/// Whether the edge from `start` to `end` is an edge of `polygon` as well.
+fn has_edge(polygon: &[Point], start: Point, end: Point) -> bool {
+    let has_corner = |point| polygon.iter().any(|&corner| same_point(corner, point));
+    has_corner(start) && has_corner(end)
+}
+
+/// The corners of a regular polygon around the origin with a circumradius of 1, starting at the
+/// given angle.
+fn regular_polygon(corners: usize, start: f32) -> Vec<Vec2> {
+    (0..corners)
+        .map(|i| Vec2::from_angle(start + 2.0 * PI * i as f32 / corners as f32))
+        .collect()
+}
+
+/// The shape marking `colour` for players who cannot tell some of the colours apart, around the
+/// origin with a radius of about 1.  The first eight colours get filled shapes, the next eight
+/// the same shapes outlined.
+fn symbol(colour: Colour) -> (Vec<Vec2>, bool) {
+    let rectangle = |width: f32, height: f32| {
+        vec![
+            vec2(-width, -height),
+            vec2(width, -height),
+            vec2(width, height),
+            vec2(-width, height),
+        ]
+    };
+    let shape = match colour.0 % 8 {
+        0 => regular_polygon(24, 0.0),
+        1 => rectangle(0.8, 0.8),
+        2 => regular_polygon(3, -PI / 2.0),
+        3 => regular_polygon(3, PI / 2.0),
+        4 => regular_polygon(4, 0.0),
+        5 => regular_polygon(6, 0.0),
+        6 => rectangle(1.0, 0.35),
+        _ => rectangle(0.35, 1.0),
+    };
+
+    (shape, colour.0 % 16 < 8)
+}
+
+pub struct Ui {
+    /// What kind of boards to generate.
+    settings: Settings,
+
+    /// Length of one unit of board coordinates in pixels.
+    scale: f32,
 
     /// Offset of the grid relative to the whole window along the horizontal axis.
     grid_x: f32,
 
     /// Offset of the grid relative to the whole window along the vertical axis.
     grid_y: f32,
+
+    /// How the game is played, e.g. against the clock.
+    mode: Mode,
+
+    /// The palettes to switch between, starting with the one given in the settings.
+    palettes: Vec<Rc<Palette>>,
+
+    /// Whether to mark every colour with its own shape.
+    show_symbols: bool,
+
+    themes: Vec<Theme>,
+
+    /// The index of the current theme in `themes`.
+    theme: usize,
+
+    animation_speed: f64,
+
+    /// The last move while it is still spreading across the board.
+    animation: Option<Animation>,
+
+    /// The colour under the mouse, either on the board or among the swatches, along with the
+    /// position of the mouse.
+    hover: Option<(Colour, (f32, f32))>,
+
+    /// The statistics shown instead of the board, as read when they were opened.
+    statistics: Option<Statistics>,
 }
 
 impl Ui {
-    pub fn create(size: u32, number_of_colours: u32) -> Ui {
-        let mut ui = Ui {
-            size,
-            number_of_colours,
-            grid_size: 0.0,
-            grid_x: 0.0,
-            grid_y: 0.0,
-        };
+    pub fn create(settings: Settings, mut mode: Mode, appearance: Appearance) -> Ui {
+        mode.start();
 
-        ui.resize();
+        // Only palettes with enough colours for every board of the mode can be switched to.
+        let number_of_colours = mode.maximum_number_of_colours(&settings) as usize;
+        let mut palettes = vec![settings.palette.clone()];
+        for name in Palette::NAMES {
+            let palette = Palette::from_name(name).unwrap();
+            if palette.len() >= number_of_colours
+                && !palettes.iter().any(|other| **other == palette)
+            {
+                palettes.push(Rc::new(palette));
+            }
+        }
 
-        ui
+        Ui {
+            settings,
+            scale: 0.0,
+            grid_x: 0.0,
+            grid_y: 0.0,
+            mode,
+            palettes,
+            show_symbols: appearance.show_symbols,
+            themes: appearance.themes,
+            theme: appearance.theme,
+            animation_speed: appearance.animation_speed,
+            animation: None,
+            hover: None,
+            statistics: None,
+        }
     }
 
-    pub fn resize(&mut self) {
+    /// Fit the board of `game` into the window.
+    pub fn resize(&mut self, game: &Game) {
         let screen_height = screen_height();
         let screen_width = screen_width();
 
-        let grid_size = screen_height.min(screen_width);
-        self.grid_size = grid_size;
-        self.grid_x = (screen_width - grid_size) / 2.0;
-        self.grid_y = (screen_height - grid_size) / 2.0;
+        let available_height = screen_height - STATUS_LINE_HEIGHT;
+        let (width, height) = game.topology.extent();
+        let scale = (available_height / height).min(screen_width / width);
+        self.scale = scale;
+        self.grid_x = (screen_width - scale * width) / 2.0;
+        self.grid_y = STATUS_LINE_HEIGHT + (available_height - scale * height) / 2.0;
+    }
+
+    fn to_screen(&self, point: Point) -> Vec2 {
+        let (x, y) = point;
+        vec2(self.grid_x + self.scale * x, self.grid_y + self.scale * y)
+    }
+
+    fn to_screen_polygon(&self, polygon: Vec<Point>) -> Vec<Vec2> {
+        polygon
+            .into_iter()
+            .map(|point| self.to_screen(point))
+            .collect()
+    }
+
+    fn to_board(&self, raw_position: (f32, f32)) -> Point {
+        let (raw_x, raw_y) = raw_position;
+        (
+            (raw_x - self.grid_x) / self.scale,
+            (raw_y - self.grid_y) / self.scale,
+        )
+    }
+
+    /// Advance the state of the game that depends on time rather than input.
+    pub fn update(&mut self, game: &mut Game, now: f64) {
+        self.mode.update(game, now);
+
+        if let Some(animation) = &mut self.animation {
+            if animation.update(now, self.animation_speed) {
+                self.animation = None;
+            }
+        }
     }
 
     pub fn render(&self, game: &Game) {
-        clear_background(BLACK);
+        let theme = &self.themes[self.theme];
+        clear_background(rgb_colour(theme.background));
 
-        let grid_x = self.grid_x;
-        let grid_y = self.grid_y;
-        let cell_size = self.cell_size();
+        if let Some(statistics) = &self.statistics {
+            self.draw_statistics(statistics, contrasting_colour(theme.background));
+            return;
+        }
+
+        if !self.mode.hides_board() {
+            let mut grid = game.grid();
+            if let Some(animation) = &self.animation {
+                animation.apply(&mut grid);
+            }
+
+            for (cell, &colour) in grid.cells.iter().enumerate() {
+                if grid.is_void(cell) {
+                    continue;
+                }
+
+                let corners = self.to_screen_polygon(grid.topology.polygon(cell));
+                let color = macroquad_colour(&self.settings.palette, colour);
+                draw_cell(
+                    &shape_cell(&corners, theme.cell_gap, theme.corner_radius),
+                    color,
+                );
+
+                // Locked cells show their lock instead.
+                if self.show_symbols && !grid.locks.contains_key(&cell) {
+                    let centre = centre(&corners);
+                    let radius = corners
+                        .iter()
+                        .map(|&corner| corner.distance(centre))
+                        .fold(f32::MAX, f32::min);
+                    self.draw_symbol(
+                        colour,
+                        centre,
+                        0.35 * radius,
+                        contrasting_colour(self.settings.palette.rgb(colour)),
+                    );
+                }
+            }
 
-        let graph = &game.graph;
-        for component in graph.neighbours.keys() {
-            for position in &graph.components[component].cells {
+            // Ghosts are drawn darker to set them apart from the actual cells.
+            for (cell, polygon) in grid.topology.ghosts() {
+                if grid.is_void(cell) {
+                    continue;
+                }
+
+                let color = macroquad_colour(&self.settings.palette, grid.cells[cell]);
+                let darker = Color::new(color.r * 0.5, color.g * 0.5, color.b * 0.5, 1.0);
+                let corners = self.to_screen_polygon(polygon);
                 draw_cell(
-                    grid_x,
-                    grid_y,
-                    cell_size,
-                    position,
-                    graph.components[component].colour,
+                    &shape_cell(&corners, theme.cell_gap, theme.corner_radius),
+                    darker,
                 );
             }
+
+            self.draw_edges(game, &grid, theme);
+
+            for (&cell, &lock) in &grid.locks {
+                self.draw_lock(&self.to_screen_polygon(grid.topology.polygon(cell)), lock);
+            }
+
+            if let Some((colour, mouse_position)) = self.hover {
+                self.draw_preview(game, &grid, colour, mouse_position);
+            }
         }
+
+        // The verdict follows the status once the game is over, shrunk to fit in front of the
+        // swatches if need be.
+        let status = match &game.verdict {
+            Some(verdict) => format!("{}   {}", self.mode.status(game), verdict),
+            None => self.mode.status(game),
+        };
+        let swatches_x = self
+            .swatches(game)
+            .last()
+            .map_or(screen_width() - self.grid_x, |(_, swatch)| swatch.x);
+        let width = measure_text(&status, None, FONT_SIZE as u16, 1.0).width;
+        let font_size =
+            FONT_SIZE * ((swatches_x - self.grid_x - FONT_SIZE) / width).clamp(0.5, 1.0);
+        draw_text(
+            &status,
+            self.grid_x,
+            self.grid_y - STATUS_LINE_HEIGHT / 4.0,
+            font_size,
+            contrasting_colour(theme.background),
+        );
+
+        for (colour, swatch) in self.swatches(game) {
+            let color = macroquad_colour(&self.settings.palette, colour);
+            draw_rectangle(swatch.x, swatch.y, swatch.w, swatch.h, color);
+
+            if self.show_symbols {
+                let radius = 0.35 * swatch.w;
+                self.draw_symbol(
+                    colour,
+                    swatch.center(),
+                    radius,
+                    contrasting_colour(self.settings.palette.rgb(colour)),
+                );
+            }
+        }
+    }
+
+    /// Draw the statistics as a table, shrinking the text if the window is too narrow for it.
+    fn draw_statistics(&self, statistics: &Statistics, color: Color) {
+        let margin = 16.0;
+        let rows = statistics.table();
+
+        let widths: Vec<f32> = (0..statistics::COLUMNS.len())
+            .map(|column| {
+                rows.iter()
+                    .map(|row| measure_text(&row[column], None, FONT_SIZE as u16, 1.0).width)
+                    .fold(0.0, f32::max)
+            })
+            .collect();
+        let total_width = widths.iter().sum::<f32>() + margin * widths.len() as f32;
+        let scale = ((screen_width() - 2.0 * margin) / total_width).min(1.0);
+        let font_size = FONT_SIZE * scale;
+
+        let mut y = STATUS_LINE_HEIGHT;
+        draw_text("Statistics (Tab to go back)", margin, y, FONT_SIZE, color);
+        y += 1.5 * STATUS_LINE_HEIGHT;
+
+        if rows.len() == 1 {
+            draw_text("No games played yet.", margin, y, FONT_SIZE, color);
+            return;
+        }
+
+        for row in &rows {
+            if y > screen_height() - 2.0 * STATUS_LINE_HEIGHT {
+                break;
+            }
+
+            let mut x = margin;
+            for (text, width) in row.iter().zip(&widths) {
+                draw_text(text, x, y, font_size, color);
+                x += scale * (width + margin);
+            }
+            y += scale * STATUS_LINE_HEIGHT;
+        }
+
+        draw_text(
+            statistics::LEGEND,
+            margin,
+            screen_height() - STATUS_LINE_HEIGHT,
+            font_size,
+            color,
+        );
     }
 
-    fn cell_size(&self) -> f32 {
-        self.grid_size / self.size as f32
+    /// The colours on the board, lined up at the right end of the status line.
+    fn swatches(&self, game: &Game) -> Vec<(Colour, Rect)> {
+        let size = STATUS_LINE_HEIGHT / 2.0;
+        let right = screen_width() - self.grid_x;
+
+        game.colours
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(i, &colour)| {
+                let x = right - size - 1.5 * size * i as f32;
+                let y = self.grid_y - 0.75 * STATUS_LINE_HEIGHT;
+                (colour, Rect::new(x, y, size, size))
+            })
+            .collect()
     }
 
-    fn cell_position(&self, raw_position: (f32, f32)) -> Option<Position> {
-        let (raw_x, raw_y) = raw_position;
+    /// Light up the cells flooding with `colour` would add and show how many there are next to
+    /// the mouse.
+    fn draw_preview(&self, game: &Game, grid: &Grid, colour: Colour, mouse_position: (f32, f32)) {
+        let captured = game.graph.captured_by(&game.start, colour);
+        if captured.is_empty() {
+            return;
+        }
 
-        let cell_size = self.cell_size();
-        let x = (raw_x - self.grid_x) / cell_size;
-        let y = (raw_y - self.grid_y) / cell_size;
+        let columns = grid.number_of_columns();
+        let highlight = Color::new(1.0, 1.0, 1.0, 0.45);
+        let mut gain = 0;
+        for position in captured.iter().flat_map(|component| &component.cells) {
+            let cell = position.row * columns + position.column;
+            let corners = self.to_screen_polygon(grid.topology.polygon(cell));
+            draw_cell(&corners, highlight);
+            gain += 1;
+        }
 
-        let size = self.size as f32;
-        if x < 0.0 || y < 0.0 || x > size || y > size {
-            // Out of bounds, nothing to do
-            None
+        let label = format!("+{} cells", gain);
+        let dimensions = measure_text(&label, None, FONT_SIZE as u16, 1.0);
+        let (x, y) = (mouse_position.0 + 16.0, mouse_position.1 + 16.0);
+        draw_rectangle(
+            x,
+            y,
+            dimensions.width + 8.0,
+            dimensions.height + 8.0,
+            Color::new(0.0, 0.0, 0.0, 0.75),
+        );
+        draw_text(
+            &label,
+            x + 4.0,
+            y + 4.0 + dimensions.offset_y,
+            FONT_SIZE,
+            WHITE,
+        );
+    }
+
+    /// Draw the borders between components and the outline of the flooded region, as far as the
+    /// theme asks for them.
+    fn draw_edges(&self, game: &Game, grid: &Grid, theme: &Theme) {
+        if theme.component_borders.is_none() && theme.flood_outline.is_none() {
+            return;
+        }
+
+        let columns = grid.number_of_columns();
+        let mut flooded = vec![false; grid.cells.len()];
+        for position in &game.graph.find_component(&game.start).cells {
+            flooded[position.row * columns + position.column] = true;
+        }
+
+        let polygons: Vec<Vec<Point>> = (0..grid.cells.len())
+            .map(|cell| grid.topology.polygon(cell))
+            .collect();
+        let mut borders = vec![];
+        let mut outline = vec![];
+
+        for cell in (0..grid.cells.len()).filter(|&cell| !grid.is_void(cell)) {
+            let polygon = &polygons[cell];
+            let neighbours = grid.neighbours(cell);
+            for i in 0..polygon.len() {
+                let (start, end) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+                let neighbour = neighbours
+                    .iter()
+                    .copied()
+                    .find(|&neighbour| has_edge(&polygons[neighbour], start, end));
+
+                // Every edge between two cells is seen from both sides; draw it only once.
+                if let Some(neighbour) = neighbour.filter(|&neighbour| neighbour > cell) {
+                    if grid.cells[cell] != grid.cells[neighbour]
+                        || grid.locks.contains_key(&cell)
+                        || grid.locks.contains_key(&neighbour)
+                    {
+                        borders.push((start, end));
+                    }
+                }
+                if flooded[cell] && !neighbour.is_some_and(|neighbour| flooded[neighbour]) {
+                    outline.push((start, end));
+                }
+            }
+        }
+
+        for (edges, colour, width) in [
+            (borders, theme.component_borders, 0.04),
+            (outline, theme.flood_outline, 0.08),
+        ] {
+            let Some(colour) = colour else {
+                continue;
+            };
+            for (start, end) in edges {
+                let (start, end) = (self.to_screen(start), self.to_screen(end));
+                draw_line(
+                    start.x,
+                    start.y,
+                    end.x,
+                    end.y,
+                    width * self.scale,
+                    rgb_colour(colour),
+                );
+            }
+        }
+    }
+
+    fn draw_symbol(&self, colour: Colour, centre: Vec2, radius: f32, color: Color) {
+        let (shape, filled) = symbol(colour);
+        let corners: Vec<Vec2> = shape
+            .into_iter()
+            .map(|corner| centre + radius * corner)
+            .collect();
+
+        if filled {
+            draw_cell(&corners, color);
         } else {
-            Some(Position {
-                column: x.floor() as usize,
-                row: y.floor() as usize,
-            })
+            for (i, &start) in corners.iter().enumerate() {
+                let end = corners[(i + 1) % corners.len()];
+                draw_line(start.x, start.y, end.x, end.y, 0.2 * radius, color);
+            }
         }
     }
 
-    fn click_while_solving(&self, game: &mut Game, position: Position) {
-        let clicked_component = game.graph.find_component(&position);
-        let colour = clicked_component.colour;
+    /// Frame a locked cell and show how many more moves or matching floods it takes to unlock it.
+    fn draw_lock(&self, corners: &[Vec2], lock: Lock) {
+        let centre = centre(corners);
+        let frame: Vec<Vec2> = corners
+            .iter()
+            .map(|&corner| centre + 0.7 * (corner - centre))
+            .collect();
+        for (i, &start) in frame.iter().enumerate() {
+            let end = frame[(i + 1) % frame.len()];
+            draw_line(start.x, start.y, end.x, end.y, 0.08 * self.scale, BLACK);
+        }
 
+        let label = match lock {
+            Lock::Moves(moves) => moves.to_string(),
+            Lock::Matches(matches) => format!("x{}", matches),
+        };
+        let font_size = (0.4 * self.scale) as u16;
+        let dimensions = measure_text(&label, None, font_size, 1.0);
+        draw_text(
+            &label,
+            centre.x - dimensions.width / 2.0,
+            centre.y + dimensions.offset_y / 2.0,
+            font_size as f32,
+            BLACK,
+        );
+    }
+
+    fn cell_position(&self, game: &Game, raw_position: (f32, f32)) -> Option<Position> {
+        let columns = game.topology.number_of_columns();
+        game.topology
+            .cell_at(self.to_board(raw_position))
+            .filter(|&cell| !game.mask.as_ref().is_some_and(|mask| mask.is_void(cell)))
+            .map(|cell| Position {
+                column: cell % columns,
+                row: cell / columns,
+            })
+    }
+
+    /// The colour under the mouse, if any: a swatch or a cell of the board.
+    fn colour_at(&self, game: &Game, mouse_position: (f32, f32)) -> Option<Colour> {
+        let swatch = self
+            .swatches(game)
+            .into_iter()
+            .find(|(_, swatch)| swatch.contains(mouse_position.into()));
+
+        match swatch {
+            Some((colour, _)) => Some(colour),
+            None => self
+                .cell_position(game, mouse_position)
+                .map(|position| game.graph.find_component(&position).colour),
+        }
+    }
+
+    fn click_while_solving(&mut self, game: &mut Game, colour: Colour) {
+        let before = game.grid();
         game.fill_component_of_top_left_cell_with(colour);
+        self.animation = if self.animation_speed > 0.0 {
+            let origin = before.first_cell();
+            Animation::new(&before, &game.grid(), origin)
+        } else {
+            None
+        };
 
         if game.graph.components.len() == 1 {
-            game.state = GameState::Solved;
-
-            if game.number_of_clicks <= game.allowed_clicks {
-                println!(
-                    "You win! You used {} out of {} available moves.",
-                    game.number_of_clicks, game.allowed_clicks,
-                );
-            } else {
-                println!(
-                    "You lose. You took {} moves but should have \
-                            finished in {}.",
-                    game.number_of_clicks, game.allowed_clicks,
-                );
+            match self.mode.board_solved(game) {
+                Continuation::Wait => (),
+                Continuation::NextBoard => self.next_board(game),
             }
         }
     }
 
-    pub fn handle_click(&self, game: &mut Game, mouse_position: (f32, f32)) {
+    pub fn handle_click(&mut self, game: &mut Game, mouse_position: (f32, f32)) {
+        if self.statistics.is_some() {
+            self.statistics = None;
+            return;
+        }
+
         match game.state {
             GameState::Solving => {
-                if let Some(position) = self.cell_position(mouse_position) {
-                    self.click_while_solving(game, position);
+                if self.mode.is_paused() {
+                    return;
+                }
+
+                if let Some(colour) = self.colour_at(game, mouse_position) {
+                    self.click_while_solving(game, colour);
                 }
             }
 
-            GameState::Solved => self.regenerate(game),
+            GameState::Solved | GameState::TimeUp => self.regenerate(game),
         }
     }
 
-    pub fn handle_key_press(&self, game: &mut Game) {
+    /// Remember what the mouse points at to preview the move.
+    pub fn handle_hover(&mut self, game: &Game, mouse_position: (f32, f32)) {
+        self.hover = match game.state {
+            GameState::Solving if !self.mode.is_paused() && self.statistics.is_none() => self
+                .colour_at(game, mouse_position)
+                .map(|colour| (colour, mouse_position)),
+            _ => None,
+        };
+    }
+
+    pub fn handle_key_press(&mut self, game: &mut Game) {
         match game.state {
-            GameState::Solving => (),
-            GameState::Solved => self.regenerate(game),
+            GameState::Solving => self.animation = None,
+            GameState::Solved | GameState::TimeUp => self.regenerate(game),
         }
     }
 
-    fn regenerate(&self, game: &mut Game) {
-        *game = Game::create(self.size, self.number_of_colours);
+    /// Show the board in the next palette, e.g. one for colour-blind players.
+    pub fn handle_palette_switch(&mut self) {
+        let current = self
+            .palettes
+            .iter()
+            .position(|palette| *palette == self.settings.palette)
+            .unwrap_or(0);
+        self.settings.palette = self.palettes[(current + 1) % self.palettes.len()].clone();
+    }
+
+    /// Switch to the next theme, along with its palette if it has one that fits the board.
+    pub fn handle_theme_switch(&mut self) {
+        self.theme = (self.theme + 1) % self.themes.len();
+
+        if let Some(palette) = &self.themes[self.theme].palette {
+            let number_of_colours = self.mode.maximum_number_of_colours(&self.settings);
+            if palette.len() >= number_of_colours as usize {
+                if !self.palettes.iter().any(|other| other == palette) {
+                    self.palettes.push(palette.clone());
+                }
+                self.settings.palette = palette.clone();
+            }
+        }
+    }
+
+    /// Show the statistics of all games instead of the board, or go back to the board.
+    pub fn handle_statistics_toggle(&mut self) {
+        self.statistics = match self.statistics {
+            Some(_) => None,
+            None => Some(Statistics::load()),
+        };
+    }
+
+    /// Count the board as lost if the player quits in the middle of it.
+    pub fn handle_quit(&self, game: &Game) {
+        self.mode.abandon(game);
+    }
+
+    pub fn handle_symbol_toggle(&mut self) {
+        self.show_symbols = !self.show_symbols;
+    }
+
+    pub fn handle_pause(&mut self, game: &Game) {
+        if let GameState::Solving = game.state {
+            self.mode.toggle_pause();
+        }
+    }
+
+    /// Start a new round.
+    fn regenerate(&mut self, game: &mut Game) {
+        self.mode.start();
+        self.next_board(game);
+    }
+
+    /// Replace the board without starting a new round.
+    fn next_board(&mut self, game: &mut Game) {
+        self.animation = None;
+        *game = self.mode.create_game(&self.settings);
     }
 }